@@ -1,7 +1,10 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use bevy::{ecs::{component::Component, system::Query}, gizmos::gizmos::Gizmos, prelude::Vec2, render::color::Color};
-use nvm_factory_sim::track::{TrackBuffer, TrackQueue};
+use core::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+
+use bevy::{ecs::{component::Component, entity::Entity, system::{Query, Res}}, gizmos::gizmos::Gizmos, log::info, prelude::Vec2, render::color::Color};
+use nvm_factory_sim::{item::{ItemRegistry, ItemStack}, power::PowerStatistics, track::{TrackBuffer, TrackQueue}};
 
 #[derive(Debug, Component)]
 pub struct ConveyorPath {
@@ -68,17 +71,43 @@ pub struct Inserter {
     pub point: Vec2,
 }
 
+/// Picks a colour for the stack's item from its registered name, so it's stable across id changes.
+/// Falls back to the raw id for unregistered items.
+#[must_use]
+pub fn item_colour(registry: Option<&ItemRegistry>, stack: ItemStack) -> Color {
+    let mut hasher = DefaultHasher::new();
+    match registry.and_then(|registry| registry.name(stack.item())) {
+        Some(name) => name.hash(&mut hasher),
+        None       => stack.item().hash(&mut hasher),
+    }
+    let h = 360.0 * ((hasher.finish() & 0xFFFF) as f32) / (u16::MAX as f32);
+    Color::hsl(h, 1.0, 0.5)
+}
+
 #[allow(clippy::missing_panics_doc)]
-pub fn render_debug_conveyors(q_conveyors: Query<(&ConveyorPath, &TrackQueue, &TrackBuffer)>, mut gizmos: Gizmos) {
+pub fn render_debug_conveyors(
+    q_conveyors: Query<(&ConveyorPath, &TrackQueue, &TrackBuffer)>, 
+    registry: Option<Res<ItemRegistry>>,
+    mut gizmos: Gizmos
+) {
     for (path, queue, buffer) in &q_conveyors {
         gizmos.linestrip_2d(path.points.iter().copied(), Color::YELLOW);
 
         for (idx, pos) in queue.iter().enumerate() {
             let stack = buffer.get(idx).unwrap();
-            let raw   = stack.to_raw().get();
-            let h = 360.0 * (((raw >> 8) ^ (raw & 0xFF)) as f32) / (u16::MAX as f32);
-            gizmos.circle_2d(path.get_item_point_on_path(pos), 5.0, Color::hsl(h, 1.0, 0.5));
+            gizmos.circle_2d(path.get_item_point_on_path(pos), 5.0, item_colour(registry.as_deref(), stack));
         }
     }
 }
 
+
+/// Logs each network's power flow over its recorded ticks.
+pub fn log_power_statistics(q_statistics: Query<(Entity, &PowerStatistics)>) {
+    for (id, statistics) in &q_statistics {
+        let sample = statistics.recent(statistics.ticks.len());
+        info!(
+            "Network {id:?} over {} ticks: generated {}, discharged {}, consumed {}, lost {}, charged {}, unmet {}",
+            statistics.ticks.len(), sample.generated, sample.discharged, sample.consumed, sample.lost, sample.charged, sample.unmet,
+        );
+    }
+}
@@ -1,32 +1,59 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use bevy::prelude::*;
-use nvm_factory_dbg::{render_debug_conveyors, ConveyorPath};
-use nvm_factory_sim::{item::ItemStack, plugin::PluginsFactory, tick::{TickPacer, TickRate1}, track::{TrackBuffer, TrackPassthrough, TrackQueue}};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use nvm_str_id::SmolStr;
+use nvm_factory_dbg::{log_power_statistics, render_debug_conveyors, ConveyorPath};
+use nvm_factory_sim::{item::ItemRegistryBuilder, plugin::PluginsFactory, power::PowerSettings, tick::{TickControl, TickMode, TickModeChanged, TickPacer, TickRate}, track::{TrackBuffer, TrackPassthrough, TrackQueue}};
 
 pub fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(PluginsFactory{
-            pacer: TickPacer::paced(24.0),
-        })
+        .add_plugins(PluginsFactory::new(TickPacer::paced(24.0)))
+        .insert_resource(PowerSettings{ line_inefficiency: 0, ticks_per_minute: 24 * 60 })
         .add_systems(Startup, setup)
+        .add_systems(Update, log_power_statistics.run_if(input_just_pressed(KeyCode::KeyP)))
+        .add_systems(PreUpdate, handle_tick_controls)
         .add_systems(PostUpdate, render_debug_conveyors)
         .run();
 }
 
+/// Space toggles pause, period steps a single tick, and 1-4 run at 1x, 2x, 10x or unbounded.
+fn handle_tick_controls(keys: Res<ButtonInput<KeyCode>>, mut control: ResMut<TickControl>, mut changes: EventReader<TickModeChanged>) {
+    let mode = match control.mode() {
+        _ if keys.just_pressed(KeyCode::Period) => TickMode::Step(1),
+        TickMode::Paused if keys.just_pressed(KeyCode::Space) => TickMode::Running(1.0),
+        _ if keys.just_pressed(KeyCode::Space)  => TickMode::Paused,
+        _ if keys.just_pressed(KeyCode::Digit1) => TickMode::Running(1.0),
+        _ if keys.just_pressed(KeyCode::Digit2) => TickMode::Running(2.0),
+        _ if keys.just_pressed(KeyCode::Digit3) => TickMode::Running(10.0),
+        _ if keys.just_pressed(KeyCode::Digit4) => TickMode::Unbounded,
+        mode => mode,
+    };
+    control.set_mode(mode);
+
+    for change in changes.read() {
+        info!("Tick mode changed from {:?} to {:?}", change.from, change.to);
+    }
+}
+
 fn setup(mut commands: Commands) {
 
+    let mut items = ItemRegistryBuilder::default();
+    let item_a = items.register(SmolStr::new("iron_plate"),   "debug".to_owned()).unwrap().as_stack(2);
+    let item_b = items.register(SmolStr::new("copper_plate"), "debug".to_owned()).unwrap().as_stack(2);
+    let items = items.build();
+    for (name, registration) in items.iter() {
+        info!("Item {:?} registered as {name}", registration.id);
+    }
+    commands.insert_resource(items);
+
     commands.spawn(Camera2dBundle{
         transform: Transform::from_translation(Vec3::new(1.0, 1.0, 0.0)*1080.0/4.0),
         ..Default::default()
     });
 
-    let item_a = ItemStack::from_raw(4096, 2);
-    let item_b = ItemStack::from_raw(4096, 2);
-
     let id_a = commands.spawn((
-        TickRate1,
+        TickRate(1),
         ConveyorPath::new(
             vec![
                 Vec2::ZERO,
@@ -49,7 +76,7 @@ fn setup(mut commands: Commands) {
 
 
     let id_b = commands.spawn((
-        TickRate1,
+        TickRate(1),
         ConveyorPath::new(
             vec![
                 Vec2::X * 60.0 * 10.0,
@@ -72,7 +99,7 @@ fn setup(mut commands: Commands) {
 
 
     let id_c = commands.spawn((
-        TickRate1,
+        TickRate(1),
         ConveyorPath::new(
             vec![
                 Vec2::Y * 60.0 * 10.0 + Vec2::X * 60.0 * 10.0,
@@ -95,7 +122,7 @@ fn setup(mut commands: Commands) {
 
 
     let id_d = commands.spawn((
-        TickRate1,
+        TickRate(1),
         ConveyorPath::new(
             vec![
                 Vec2::Y * 60.0 * 10.0,
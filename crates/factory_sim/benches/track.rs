@@ -0,0 +1,65 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+//! Conveyor hot path benchmarks. Compare stack layouts with:
+//! `cargo bench -p nvm_factory_sim` and `cargo bench -p nvm_factory_sim --features wide_stacks`
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use nvm_factory_sim::{
+    item::ItemStack,
+    plugin::PluginsFactory,
+    tick::TickRate,
+    track::{TrackBuffer, TrackPassthrough, TrackQueue, TrackSpeed, TRACK_MAX_ITEMS}
+};
+
+/// Creates loops of two tracks, half filled with items, each with the given rate or speed.
+fn create_app(loops: usize, rate: impl Bundle + Clone) -> App {
+    let mut app = App::new();
+    app.add_plugins(PluginsFactory::default());
+
+    let stack  = ItemStack::from_raw(1, 1);
+    let queue  = (0..TRACK_MAX_ITEMS).step_by(2).fold(TrackQueue::default(), TrackQueue::with);
+    let buffer = {
+        let mut buffer = TrackBuffer::default();
+        for _ in 0..TRACK_MAX_ITEMS/2 {
+            buffer.push(stack).unwrap();
+        }
+        buffer
+    };
+
+    for _ in 0..loops {
+        let a = app.world.spawn((queue, buffer, rate.clone())).id();
+        let b = app.world.spawn((queue, buffer, rate.clone(), TrackPassthrough::new_end_to_end(a))).id();
+        app.world.entity_mut(a).insert(TrackPassthrough::new_end_to_end(b));
+    }
+
+    app
+}
+
+fn bench_conveyors(c: &mut Criterion) {
+    let layout = if cfg!(feature = "wide_stacks") { "wide" } else { "narrow" };
+    let mut group = c.benchmark_group(format!("conveyors_{layout}"));
+    for loops in [100, 1_000, 10_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(loops), &loops, |b, &loops| {
+            let mut app = create_app(loops, TickRate(1));
+            b.iter(|| app.update());
+        });
+    }
+    group.finish();
+}
+
+fn bench_conveyors_fractional(c: &mut Criterion) {
+    let layout = if cfg!(feature = "wide_stacks") { "wide" } else { "narrow" };
+    let mut group = c.benchmark_group(format!("conveyors_fractional_{layout}"));
+    for loops in [100, 1_000, 10_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(loops), &loops, |b, &loops| {
+            let mut app = create_app(loops, TrackSpeed::new(3, 2));
+            b.iter(|| app.update());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_conveyors, bench_conveyors_fractional);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+use nvm_str_id::SmolStr;
+
+use super::{Item, ItemRegistry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemFilterMode {
+    /// Only items matching an entry pass.
+    Whitelist,
+    /// Only items matching no entry pass.
+    Blacklist,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemFilterEntry {
+    Item(Item),
+    /// Matches items with the given tag in the [`ItemRegistry`].
+    Tag(SmolStr),
+}
+
+impl ItemFilterEntry {
+
+    #[must_use]
+    pub fn matches(&self, item: Item, registry: Option<&ItemRegistry>) -> bool {
+        match self {
+            Self::Item(other) => *other == item,
+            Self::Tag(tag)    => registry.is_some_and(|registry| registry.info(item).has_tag(*tag)),
+        }
+    }
+
+}
+
+/// Restricts which items an inserter or extractor will move. 
+/// Tags can't be matched without an [`ItemRegistry`] resource, and will never match.
+#[derive(Debug, Clone, Component, PartialEq, Eq)]
+pub struct ItemFilter {
+    pub mode:    ItemFilterMode,
+    pub entries: Vec<ItemFilterEntry>,
+}
+
+impl ItemFilter {
+
+    #[must_use]
+    pub fn whitelist(entries: impl IntoIterator<Item = ItemFilterEntry>) -> Self {
+        Self{ mode: ItemFilterMode::Whitelist, entries: entries.into_iter().collect() }
+    }
+
+    #[must_use]
+    pub fn blacklist(entries: impl IntoIterator<Item = ItemFilterEntry>) -> Self {
+        Self{ mode: ItemFilterMode::Blacklist, entries: entries.into_iter().collect() }
+    }
+
+    #[must_use]
+    pub fn matches(&self, item: Item, registry: Option<&ItemRegistry>) -> bool {
+        let found = self.entries.iter().any(|entry| entry.matches(item, registry));
+        match self.mode {
+            ItemFilterMode::Whitelist => found,
+            ItemFilterMode::Blacklist => !found,
+        }
+    }
+
+    /// Checks an optional filter, where no filter matches everything.
+    #[must_use]
+    pub fn allows(filter: Option<&Self>, item: Item, registry: Option<&ItemRegistry>) -> bool {
+        filter.is_none_or(|filter| filter.matches(item, registry))
+    }
+
+}
@@ -0,0 +1,50 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use std::collections::BTreeMap;
+
+use nvm_str_id::SmolStr;
+
+use super::ITEM_STACK_MAX;
+
+/// Per-item properties, stored densely in the [`ItemRegistry`](super::ItemRegistry).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemInfo {
+    pub display_name: String,
+    /// Clamped to `1..=ITEM_STACK_MAX` when the registry is built.
+    pub max_stack:    u16,
+    pub tags:         Vec<SmolStr>,
+    pub sort_order:   i32,
+    pub metadata:     BTreeMap<String, String>,
+}
+
+impl Default for ItemInfo {
+    fn default() -> Self {
+        Self {
+            display_name: String::new(),
+            max_stack:    ITEM_STACK_MAX,
+            tags:         Vec::new(),
+            sort_order:   0,
+            metadata:     BTreeMap::new(),
+        }
+    }
+}
+
+impl ItemInfo {
+
+    #[must_use]
+    pub fn has_tag(&self, tag: SmolStr) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    pub fn add_tag(&mut self, tag: SmolStr) {
+        if !self.has_tag(tag) {
+            self.tags.push(tag);
+        }
+    }
+
+    #[must_use]
+    pub const fn can_stack(&self, size: usize) -> bool {
+        size <= self.max_stack as usize
+    }
+
+}
@@ -1,15 +1,45 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use core::num::NonZeroU16;
-
 mod stack;
 pub use stack::*;
 
 mod registry;
 pub use registry::*;
 
+mod info;
+pub use info::*;
+
+mod filter;
+pub use filter::*;
+
+#[cfg(test)]
+mod test;
+
+/// The integer [`Item`]s and [`ItemStack`]s are packed into. The `wide_stacks` feature
+/// widens this to 32 bits, for a 20-bit id and 12-bit count.
+#[cfg(not(feature = "wide_stacks"))]
+pub type ItemRaw = u16;
+#[cfg(not(feature = "wide_stacks"))]
+pub type ItemRawNonZero = core::num::NonZeroU16;
+#[cfg(not(feature = "wide_stacks"))]
+pub const ITEM_ID_BITS: u32 = 12;
+
+#[cfg(feature = "wide_stacks")]
+pub type ItemRaw = u32;
+#[cfg(feature = "wide_stacks")]
+pub type ItemRawNonZero = core::num::NonZeroU32;
+#[cfg(feature = "wide_stacks")]
+pub const ITEM_ID_BITS: u32 = 20;
+
+/// The largest item id, and the mask of the id bits in an [`ItemStack`].
+pub const ITEM_ID_MAX: ItemRaw = (1 << ITEM_ID_BITS) - 1;
+
+/// The most items that fit in an [`ItemStack`].
+#[allow(clippy::unnecessary_cast)] // Only unnecessary for the narrow layout
+pub const ITEM_STACK_MAX: u16 = (ItemRaw::MAX >> ITEM_ID_BITS) as u16;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Item(NonZeroU16);
+pub struct Item(ItemRawNonZero);
 
 impl Item {
 
@@ -21,7 +51,7 @@ impl Item {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub const fn from_stack(other: ItemStack) -> Item {
-        if let Some(other) = NonZeroU16::new(other.to_raw().get() & 0x0FFF) {
+        if let Some(other) = ItemRawNonZero::new(other.to_raw().get() & ITEM_ID_MAX) {
             Self(other)
         } else {
             panic!("Couldn't extract item from stack");
@@ -29,8 +59,20 @@ impl Item {
     }
 
     #[must_use]
-    pub const fn to_raw(self) -> NonZeroU16 {
+    pub const fn to_raw(self) -> ItemRawNonZero {
         self.0
     }
 
+    /// Recreates an item from [`Item::to_raw`], if it's a valid id.
+    #[must_use]
+    pub const fn from_raw(raw: ItemRaw) -> Option<Item> {
+        if raw & ITEM_ID_MAX != raw {
+            return None;
+        }
+        match ItemRawNonZero::new(raw) {
+            Some(v) => Some(Self(v)),
+            None    => None,
+        }
+    }
+
 }
\ No newline at end of file
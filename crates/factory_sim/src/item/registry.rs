@@ -1,33 +1,56 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use core::{num::NonZeroU16, fmt::Debug};
+use core::fmt::Debug;
 
-use bevy::utils::{Entry, HashMap};
+use bevy::{prelude::*, utils::{Entry, HashMap, HashSet}};
 use nvm_str_id::SmolStr;
 
-use super::Item;
+use super::{Item, ItemInfo, ItemRaw, ItemRawNonZero, ITEM_ID_MAX, ITEM_STACK_MAX};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemProvenanceKind {
+    Registered,
+    Replaced,
+    Patched,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemProvenance {
+    pub owner: String,
+    pub kind:  ItemProvenanceKind,
+}
 
 pub struct ItemRegistration {
     pub owner: String,
     pub id:    Item,
+    /// Every owner that registered, replaced or patched this item, in order.
+    pub provenance: Vec<ItemProvenance>,
+    pub info: ItemInfo,
 }
 
 pub struct ItemRegistryBuilder {
     lookup: HashMap<SmolStr, ItemRegistration>,
-    remaining: u16,
+    next: ItemRaw,
+    previous: HashMap<SmolStr, Item>,
+    reserved: HashSet<Item>,
 }
 
 impl Default for ItemRegistryBuilder {
     fn default() -> Self {
         Self { 
             lookup: HashMap::default(), 
-            remaining: 0x0FFF,
+            // Id 1 is left for [`ItemStack::EMPTY`](super::ItemStack::EMPTY)
+            next: 2,
+            previous: HashMap::default(),
+            reserved: HashSet::default(),
         }
     }
 }
 
 pub enum ItemRegistryBuilderError {
     AlreadyRegistered(SmolStr, Item, String),
+    NotRegistered(SmolStr, String),
+    DuplicateMapping(SmolStr, SmolStr, Item),
     ExhaustedIds,
 }
 
@@ -37,6 +60,12 @@ impl Debug for ItemRegistryBuilderError {
             Self::AlreadyRegistered(arg0, arg1, arg2) => {
                 write!(f, "ItemRegistry: Item ({arg0}) is already registered as ({arg1:?}) by ({arg2})")
             },
+            Self::NotRegistered(arg0, arg1) => {
+                write!(f, "ItemRegistry: Item ({arg0}) can't be modified by ({arg1}) as it isn't registered")
+            },
+            Self::DuplicateMapping(arg0, arg1, arg2) => {
+                write!(f, "ItemRegistry: Items ({arg0}) and ({arg1}) are both mapped to ({arg2:?})")
+            },
             Self::ExhaustedIds => write!(f, "ItemRegistry: All IDs exhausted"),
         }
     }
@@ -44,7 +73,28 @@ impl Debug for ItemRegistryBuilderError {
 
 impl ItemRegistryBuilder {
 
+    /// Creates a builder that keeps the ids from a previous registry's [`ItemRegistry::mapping`]. 
+    /// Ids in the mapping are never handed out to other items, even if their item is removed.
+    /// Items mapped to the reserved id 1 are given a new id instead.
+    pub fn with_mapping(previous: impl IntoIterator<Item = (SmolStr, Item)>) -> Result<Self, ItemRegistryBuilderError> {
+        let mut result = Self::default();
+        let mut owners: HashMap<Item, SmolStr> = HashMap::default();
+        for (name, id) in previous {
+            if id.to_raw() == ItemRawNonZero::MIN {
+                continue;
+            }
+            if let Some(&other) = owners.get(&id) {
+                return Err(ItemRegistryBuilderError::DuplicateMapping(other, name, id));
+            }
+            owners.insert(id, name);
+            result.previous.insert(name, id);
+            result.reserved.insert(id);
+        }
+        Ok(result)
+    }
+
     /// Registers an item, as being registered by the given owner, and returns the registered id.
+    /// If the item was in the previous mapping it'll keep its id, otherwise the next free id is used.
     pub fn register(&mut self, name: SmolStr, owner: String) -> Result<Item, ItemRegistryBuilderError> {
         match self.lookup.entry(name) {
             Entry::Occupied(v) => {
@@ -52,44 +102,142 @@ impl ItemRegistryBuilder {
                 Err(ItemRegistryBuilderError::AlreadyRegistered(name, entry.id, entry.owner.clone()))
             },
             Entry::Vacant(v) => {
-                if self.remaining == 0 {
-                    Err(ItemRegistryBuilderError::ExhaustedIds)
+                let id = if let Some(&id) = self.previous.get(&name) {
+                    id
                 } else {
-                    if self.remaining == 0 { return Err(ItemRegistryBuilderError::ExhaustedIds); }
-                    let next = NonZeroU16::new(self.remaining);
-                    self.remaining -= 1;
-                    match next {
-                        Some(next) => Ok(v.insert(ItemRegistration{ id: Item(next), owner, }).id),
-                        None => unreachable!()
+                    loop {
+                        let Some(next) = ItemRawNonZero::new(self.next).filter(|_| self.next <= ITEM_ID_MAX) else {
+                            return Err(ItemRegistryBuilderError::ExhaustedIds);
+                        };
+                        self.next += 1;
+                        if !self.reserved.contains(&Item(next)) {
+                            break Item(next);
+                        }
                     }
-                }
+                };
+
+                let provenance = vec![ItemProvenance{ owner: owner.clone(), kind: ItemProvenanceKind::Registered }];
+                Ok(v.insert(ItemRegistration{ owner, id, provenance, info: ItemInfo::default() }).id)
             }
         }
     }
 
+    /// Items from the previous mapping that haven't been registered.
+    pub fn removed(&self) -> impl Iterator<Item = (SmolStr, Item)> + '_ {
+        self.previous.iter().filter(|(name, _)| !self.lookup.contains_key(*name)).map(|(&name, &id)| (name, id))
+    }
+
+    /// Replaces an existing registration, transferring ownership to the given owner while keeping its id.
+    pub fn replace(&mut self, name: SmolStr, owner: String) -> Result<Item, ItemRegistryBuilderError> {
+        let Some(entry) = self.lookup.get_mut(&name) else {
+            return Err(ItemRegistryBuilderError::NotRegistered(name, owner));
+        };
+        entry.provenance.push(ItemProvenance{ owner: owner.clone(), kind: ItemProvenanceKind::Replaced });
+        entry.owner = owner;
+        Ok(entry.id)
+    }
+
+    /// Patches an existing registration, recording the given owner without transferring ownership.
+    pub fn patch(&mut self, name: SmolStr, owner: String) -> Result<Item, ItemRegistryBuilderError> {
+        let Some(entry) = self.lookup.get_mut(&name) else {
+            return Err(ItemRegistryBuilderError::NotRegistered(name, owner));
+        };
+        entry.provenance.push(ItemProvenance{ owner, kind: ItemProvenanceKind::Patched });
+        Ok(entry.id)
+    }
+
     #[must_use]
     pub fn get(&mut self, name: SmolStr) -> Option<&ItemRegistration> {
         self.lookup.get(&name)
     }
+
+    #[must_use]
+    pub fn info_mut(&mut self, name: SmolStr) -> Option<&mut ItemInfo> {
+        self.lookup.get_mut(&name).map(|v| &mut v.info)
+    }
     
     #[must_use]
-    pub fn build(self) -> ItemRegistry {
+    pub fn build(mut self) -> ItemRegistry {
+        // Ids are allocated counting up, so sizing to the largest id keeps the tables dense
+        let len = self.lookup.values().map(|v| v.id.to_raw().get() as usize + 1).max().unwrap_or(0);
+        let mut table = vec![ItemInfo::default(); len];
+        let mut names = vec![None; len];
+        for (&name, registration) in &mut self.lookup {
+            registration.info.max_stack = registration.info.max_stack.clamp(1, ITEM_STACK_MAX);
+            table[registration.id.to_raw().get() as usize] = registration.info.clone();
+            names[registration.id.to_raw().get() as usize] = Some(name);
+        }
+
         ItemRegistry{
-            lookup: self.lookup
+            lookup: self.lookup,
+            table,
+            names,
+            fallback: ItemInfo::default(),
         }
     }
 
 }
 
+#[derive(Resource)]
 pub struct ItemRegistry {
     lookup: HashMap<SmolStr, ItemRegistration>,
+    table:  Vec<ItemInfo>,
+    names:  Vec<Option<SmolStr>>,
+    fallback: ItemInfo,
 }
 
 impl ItemRegistry {
 
     #[must_use]
-    pub fn get(&mut self, id: SmolStr) -> Option<&ItemRegistration> {
+    pub fn get(&self, id: SmolStr) -> Option<&ItemRegistration> {
         self.lookup.get(&id)
     }
 
+    /// The name the given item was registered under.
+    #[must_use]
+    pub fn name(&self, item: Item) -> Option<SmolStr> {
+        self.names.get(item.to_raw().get() as usize).copied().flatten()
+    }
+
+    #[must_use]
+    pub fn get_by_id(&self, item: Item) -> Option<&ItemRegistration> {
+        self.lookup.get(&self.name(item)?)
+    }
+
+    /// Every registration, in id order.
+    pub fn iter(&self) -> impl Iterator<Item = (SmolStr, &ItemRegistration)> + '_ {
+        self.names.iter().filter_map(|name| {
+            let name = (*name)?;
+            Some((name, &self.lookup[&name]))
+        })
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lookup.len()
+    }
+
+    /// The length of the tables indexed by id, one more than the largest registered id.
+    #[must_use]
+    pub const fn id_capacity(&self) -> usize {
+        self.table.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lookup.is_empty()
+    }
+
+    /// The properties of the given item, unregistered items have the default properties.
+    #[must_use]
+    pub fn info(&self, item: Item) -> &ItemInfo {
+        self.table.get(item.to_raw().get() as usize).unwrap_or(&self.fallback)
+    }
+
+    /// The name to id mapping, to be persisted and passed to [`ItemRegistryBuilder::with_mapping`] 
+    /// when the registry is next built.
+    pub fn mapping(&self) -> impl Iterator<Item = (SmolStr, Item)> + '_ {
+        self.lookup.iter().map(|(&name, registration)| (name, registration.id))
+    }
+
 }
@@ -1,22 +1,22 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use core::num::NonZeroU16;
-
-use super::Item;
+use super::{Item, ItemRaw, ItemRawNonZero, ITEM_ID_BITS, ITEM_ID_MAX, ITEM_STACK_MAX};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct ItemStack(NonZeroU16);
+pub struct ItemStack(ItemRawNonZero);
 
 impl ItemStack {
 
-    pub const EMPTY: ItemStack = ItemStack(NonZeroU16::MIN);
+    /// A placeholder stack, for filling storage. This uses id 1, which the [`ItemRegistryBuilder`](super::ItemRegistryBuilder)
+    /// never hands out, so it's distinct from empty stacks of registered items. Use [`ItemStack::is_empty`] to check for empty stacks.
+    pub const EMPTY: ItemStack = ItemStack(ItemRawNonZero::MIN);
 
     /// # Panics
-    /// - If size >= 16
+    /// - If size > [`ITEM_STACK_MAX`]
     #[must_use]
     pub const fn new(item: Item, size: usize) -> Self {
-        assert!(size & 0x0F == size);
-        if let Some(v) = NonZeroU16::new(item.0.get() | (size as u16) << 12) {
+        assert!(size <= ITEM_STACK_MAX as usize);
+        if let Some(v) = ItemRawNonZero::new(item.0.get() | (size as ItemRaw) << ITEM_ID_BITS) {
             Self(v)
         } else {
             panic!("Couldn't construct item stack. This isn't possible");
@@ -30,27 +30,99 @@ impl ItemStack {
 
     #[must_use]
     pub const fn size(self) -> usize {
-        (self.0.get() >> 12) as usize
+        (self.0.get() >> ITEM_ID_BITS) as usize
     }
 
     #[must_use]
-    pub const fn to_raw(self) -> NonZeroU16 {
+    pub const fn to_raw(self) -> ItemRawNonZero {
         self.0
     }
+
+    /// An empty stack, that'll only merge with stacks of the given item.
+    #[must_use]
+    pub const fn empty(item: Item) -> Self {
+        Self::new(item, 0)
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.size() == 0
+    }
+
+    #[must_use]
+    pub const fn is_full(self) -> bool {
+        self.size() == ITEM_STACK_MAX as usize
+    }
+
+    /// The number of items that can still be added to this stack.
+    #[must_use]
+    pub const fn capacity(self) -> usize {
+        ITEM_STACK_MAX as usize - self.size()
+    }
+
+    #[must_use]
+    pub const fn is_same_item(self, other: Self) -> bool {
+        self.0.get() & ITEM_ID_MAX == other.0.get() & ITEM_ID_MAX
+    }
+
+    /// # Panics
+    /// - If size > [`ITEM_STACK_MAX`]
+    #[must_use]
+    pub const fn with_size(self, size: usize) -> Self {
+        Self::new(self.item(), size)
+    }
+
+    /// Merges the other stack into this one, returning the merged stack and the items that
+    /// didn't fit. Stacks of different items can't be merged, and return the other stack as an error.
+    pub const fn merge(self, other: Self) -> Result<(Self, Self), Self> {
+        if !self.is_same_item(other) {
+            return Err(other);
+        }
+
+        let moved = if other.size() < self.capacity() { other.size() } else { self.capacity() };
+        Ok((self.with_size(self.size() + moved), other.with_size(other.size() - moved)))
+    }
+
+    /// Splits off up to `size` items, returning them and the remainder.
+    #[must_use]
+    pub const fn split(self, size: usize) -> (Self, Self) {
+        let size = if size < self.size() { size } else { self.size() };
+        (self.with_size(size), self.with_size(self.size() - size))
+    }
+
+    /// Takes up to `size` items out of this stack.
+    #[must_use]
+    pub fn take(&mut self, size: usize) -> Self {
+        let (taken, remainder) = self.split(size);
+        *self = remainder;
+        taken
+    }
+
+    /// Moves up to `max` items from this stack into `dst`, returning the number of items moved.
+    /// Nothing is moved if the stacks are of different items.
+    pub fn transfer(&mut self, dst: &mut Self, max: usize) -> usize {
+        if !self.is_same_item(*dst) {
+            return 0;
+        }
+
+        let taken = self.take(max.min(dst.capacity()));
+        *dst = dst.with_size(dst.size() + taken.size());
+        taken.size()
+    }
 }
 
 impl ItemStack {
 
     /// # Panics
-    /// - If amount >= 16
+    /// - If amount > [`ITEM_STACK_MAX`]
     #[must_use]
-    pub const fn from_raw(item: u16, size: usize) -> Self {
-        assert!(size & 0x0F == size);
-        if let Some(v) = NonZeroU16::new(item | (size as u16) << 12) {
+    pub const fn from_raw(item: ItemRaw, size: usize) -> Self {
+        assert!(size <= ITEM_STACK_MAX as usize);
+        if let Some(v) = ItemRawNonZero::new(item | (size as ItemRaw) << ITEM_ID_BITS) {
             Self(v)
         } else {
             panic!("Couldn't construct item stack. This isn't possible");
         }
     }
 
-}
\ No newline at end of file
+}
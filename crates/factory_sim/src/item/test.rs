@@ -0,0 +1,119 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use nvm_str_id::SmolStr;
+
+use crate::item::{Item, ItemRegistryBuilder, ItemRegistryBuilderError, ItemStack, ITEM_ID_MAX, ITEM_STACK_MAX};
+
+#[test]
+pub fn test_stack_merge_split() {
+    // Registered rather than raw ids, so iron has the first id handed out
+    let mut builder = ItemRegistryBuilder::default();
+    let iron   = builder.register(SmolStr::new("iron"),   "base".to_owned()).unwrap();
+    let copper = builder.register(SmolStr::new("copper"), "base".to_owned()).unwrap();
+    let max    = ITEM_STACK_MAX as usize;
+
+    // Overflow is returned as the remainder
+    assert_eq!(Ok((iron.as_stack(max), iron.as_stack(5))), iron.as_stack(max - 5).merge(iron.as_stack(10)));
+    assert_eq!(Ok((iron.as_stack(7), ItemStack::empty(iron))), ItemStack::empty(iron).merge(iron.as_stack(7)));
+    assert_eq!(Err(copper.as_stack(1)), iron.as_stack(1).merge(copper.as_stack(1)));
+
+    assert_eq!((iron.as_stack(4), iron.as_stack(6)), iron.as_stack(10).split(4));
+    assert_eq!((iron.as_stack(3), ItemStack::empty(iron)), iron.as_stack(3).split(8));
+
+    let mut stack = iron.as_stack(5);
+    assert_eq!(iron.as_stack(2), stack.take(2));
+    assert_eq!(iron.as_stack(3), stack);
+
+    // Empty stacks keep their item, unlike EMPTY
+    let empty = ItemStack::empty(iron);
+    assert!(empty.is_empty() && ItemStack::EMPTY.is_empty());
+    assert_ne!(ItemStack::EMPTY, empty);
+    assert!(!ItemStack::EMPTY.is_same_item(iron.as_stack(1)));
+    assert_eq!(iron, empty.item());
+}
+
+#[test]
+pub fn test_stack_transfer() {
+    let iron   = Item::from_raw(2).unwrap();
+    let copper = Item::from_raw(3).unwrap();
+    let max    = ITEM_STACK_MAX as usize;
+
+    let mut src = iron.as_stack(10);
+    let mut dst = iron.as_stack(max - 3);
+    assert_eq!(3, src.transfer(&mut dst, 5));
+    assert_eq!((iron.as_stack(7), iron.as_stack(max)), (src, dst));
+    assert!(dst.is_full());
+
+    let mut dst = ItemStack::empty(iron);
+    assert_eq!(2, src.transfer(&mut dst, 2));
+    assert_eq!((iron.as_stack(5), iron.as_stack(2)), (src, dst));
+
+    let mut dst = copper.as_stack(1);
+    assert_eq!(0, src.transfer(&mut dst, 5));
+    assert_eq!(copper.as_stack(1), dst);
+}
+
+#[test]
+pub fn test_registry_mapping() {
+    let [iron, copper, gold, tin] = [SmolStr::new("iron"), SmolStr::new("copper"), SmolStr::new("gold"), SmolStr::new("tin")];
+
+    let mut builder = ItemRegistryBuilder::default();
+    let iron_id   = builder.register(iron,   "base".to_owned()).unwrap();
+    let copper_id = builder.register(copper, "base".to_owned()).unwrap();
+    let gold_id   = builder.register(gold,   "base".to_owned()).unwrap();
+    let previous  = builder.build();
+
+    // Reordered, with an item removed and another added
+    let mut builder = ItemRegistryBuilder::with_mapping(previous.mapping()).unwrap();
+    let tin_id = builder.register(tin, "base".to_owned()).unwrap();
+    assert_eq!(gold_id, builder.register(gold, "base".to_owned()).unwrap());
+    assert_eq!(iron_id, builder.register(iron, "base".to_owned()).unwrap());
+    assert!(![iron_id, copper_id, gold_id].contains(&tin_id));
+    assert_eq!(vec![(copper, copper_id)], builder.removed().collect::<Vec<_>>());
+
+    // Mapping is rejected if ids are shared
+    assert!(matches!(
+        ItemRegistryBuilder::with_mapping([(iron, iron_id), (tin, iron_id)]),
+        Err(ItemRegistryBuilderError::DuplicateMapping(a, b, id)) if a == iron && b == tin && id == iron_id
+    ));
+}
+
+#[test]
+pub fn test_registry_lookup() {
+    let [iron, copper, gold] = [SmolStr::new("iron"), SmolStr::new("copper"), SmolStr::new("gold")];
+
+    let mut builder = ItemRegistryBuilder::default();
+    let iron_id   = builder.register(iron,   "base".to_owned()).unwrap();
+    let copper_id = builder.register(copper, "base".to_owned()).unwrap();
+    let gold_id   = builder.register(gold,   "base".to_owned()).unwrap();
+    let registry  = builder.build();
+
+    assert_eq!(3, registry.len());
+    assert_eq!(Some(copper), registry.name(copper_id));
+    assert_eq!(None, registry.name(Item::from_raw(ITEM_ID_MAX).unwrap()));
+    assert_eq!(gold_id, registry.get_by_id(gold_id).unwrap().id);
+
+    let mut expected = vec![(iron, iron_id), (copper, copper_id), (gold, gold_id)];
+    expected.sort_by_key(|&(_, id)| id);
+    assert_eq!(expected, registry.iter().map(|(name, registration)| (name, registration.id)).collect::<Vec<_>>());
+}
+
+#[test]
+pub fn test_registry_ids_dense() {
+    let mut builder = ItemRegistryBuilder::default();
+    let ids: Vec<_> = ["iron", "copper", "gold"].into_iter().map(|name| builder.register(SmolStr::new(name), "base".to_owned()).unwrap()).collect();
+    let registry = builder.build();
+
+    // Ids count up from 2, so the tables only cover the registered ids regardless of the id space
+    assert_eq!(vec![2, 3, 4], ids.iter().map(|id| id.to_raw().get()).collect::<Vec<_>>());
+    assert_eq!(5, registry.id_capacity());
+
+    // Id 1 is left for EMPTY, so it never aliases an empty stack of a registered item
+    assert_ne!(ItemStack::EMPTY, ItemStack::empty(ids[0]));
+    assert!(!ItemStack::EMPTY.is_same_item(ids[0].as_stack(1)));
+
+    // Including items that were mapped to it by a previous registry
+    let iron = SmolStr::new("iron");
+    let mut builder = ItemRegistryBuilder::with_mapping([(iron, Item::from_raw(1).unwrap())]).unwrap();
+    assert_eq!(2, builder.register(iron, "base".to_owned()).unwrap().to_raw().get());
+}
@@ -5,10 +5,17 @@ pub mod item;
 pub mod track;
 pub mod tick;
 pub mod power;
+pub mod machine;
+pub mod pack;
 pub mod plugin;
 
+#[cfg(test)]
+mod test;
+
 pub mod prelude {
     pub use super::plugin::*;
     pub use super::item::*;
     pub use super::track::*;
+    pub use super::power::*;
+    pub use super::machine::*;
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::item::{Item, ItemStack, ITEM_STACK_MAX};
+
+mod system;
+pub use system::*;
+
+mod plugin;
+pub use plugin::*;
+
+mod registry;
+pub use registry::*;
+
+#[cfg(test)]
+mod test;
+
+// // //
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecipeEntry {
+    pub item:  Item,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub inputs:   Vec<RecipeEntry>,
+    pub outputs:  Vec<RecipeEntry>,
+    /// Ticks taken to craft once.
+    pub duration: u32,
+    /// Power drawn while crafting, if any.
+    pub power:    Option<u16>,
+}
+
+// // //
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrafterSlot {
+    pub item:  Item,
+    pub count: u32,
+    pub limit: u32,
+}
+
+impl CrafterSlot {
+
+    /// Creates a slot that'll hold twice the recipe's requirement, plus room for a full stack.
+    #[must_use]
+    pub const fn for_entry(entry: RecipeEntry) -> Self {
+        Self {
+            item:  entry.item,
+            count: 0,
+            limit: entry.count * 2 + ITEM_STACK_MAX as u32,
+        }
+    }
+
+    #[must_use]
+    pub const fn can_insert(&self, stack: ItemStack) -> bool {
+        stack.item().to_raw().get() == self.item.to_raw().get() && self.count + stack.size() as u32 <= self.limit
+    }
+
+    /// Takes up to `max` items out of the slot, as a single stack.
+    pub fn take_stack(&mut self, max: u16) -> Option<ItemStack> {
+        let size = self.count.min(u32::from(max.min(ITEM_STACK_MAX)));
+        (size > 0).then(|| {
+            self.count -= size;
+            self.item.as_stack(size as usize)
+        })
+    }
+
+}
+
+/// Crafts a [`Recipe`] from the items in its input slots, into its output slots.
+/// 
+/// Recipes that need power only start or progress while the crafter's [`PowerSink`](crate::power::PowerSink)
+/// is supplied power and not [`PowerStalled`](crate::power::PowerStalled), so they slow down in proportion to their supply. 
+/// For these recipes the crafter owns its sink's `max`, setting it to the recipe's power while it has work and `0` otherwise, 
+/// even while stalled, while `min` is left as configured.
+#[derive(Debug, Clone, Component)]
+pub struct Crafter {
+    pub recipe:   Arc<Recipe>,
+    pub inputs:   Vec<CrafterSlot>,
+    pub outputs:  Vec<CrafterSlot>,
+    /// Ticks spent on the current craft, if crafting.
+    pub progress: Option<u32>,
+}
+
+impl Crafter {
+
+    #[must_use]
+    pub fn new(recipe: Arc<Recipe>) -> Self {
+        Self {
+            inputs:   recipe.inputs.iter().copied().map(CrafterSlot::for_entry).collect(),
+            outputs:  recipe.outputs.iter().copied().map(CrafterSlot::for_entry).collect(),
+            progress: None,
+            recipe,
+        }
+    }
+
+    #[must_use]
+    pub fn can_start(&self) -> bool {
+        self.recipe.inputs.iter().zip(&self.inputs).all(|(entry, slot)| slot.count >= entry.count)
+            && self.recipe.outputs.iter().zip(&self.outputs).all(|(entry, slot)| slot.count + entry.count <= slot.limit)
+    }
+
+    /// Inserts the stack into the first input slot that accepts it, or returns it if none do.
+    pub fn insert(&mut self, stack: ItemStack) -> Result<(), ItemStack> {
+        let slot = self.inputs.iter_mut().find(|slot| slot.can_insert(stack)).ok_or(stack)?;
+        slot.count += stack.size() as u32;
+        Ok(())
+    }
+
+    /// Takes up to a full stack out of the given output slot.
+    pub fn extract(&mut self, slot: usize) -> Option<ItemStack> {
+        self.extract_at_most(slot, ITEM_STACK_MAX)
+    }
+
+    /// Takes up to `max` items out of the given output slot.
+    pub fn extract_at_most(&mut self, slot: usize, max: u16) -> Option<ItemStack> {
+        self.outputs.get_mut(slot)?.take_stack(max)
+    }
+
+}
+
+// // //
+
+/// Moves the contents of its [`StackBuffer`](crate::track::StackBuffer) into the target [`Crafter`]'s input slots.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CrafterInserter {
+    pub target:   Entity,
+    pub cooldown: u32,
+}
+
+/// Moves a stack from the target [`Crafter`]'s output slot into its [`StackBuffer`](crate::track::StackBuffer).
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CrafterExtractor {
+    pub target:   Entity,
+    pub slot:     usize,
+    pub cooldown: u32,
+}
@@ -0,0 +1,31 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::{ecs::{query::QueryFilter, schedule::SystemConfigs}, prelude::*};
+
+use crate::{
+    tick::{AppSubTickExt, FilterSubTick, SubTick, SubTickSystems}, 
+    track::handle_track_stack_inserters
+};
+
+use super::{handle_crafter_extractors, handle_crafter_inserters, update_crafters};
+
+pub struct PluginMachine;
+
+struct MachineSystems;
+
+impl SubTickSystems for MachineSystems {
+    fn build<F: QueryFilter + 'static>(&self, _sub_tick: SubTick) -> SystemConfigs {
+        (
+            handle_crafter_inserters::<F>,
+            handle_crafter_extractors::<F>,
+        ).chain().after(handle_track_stack_inserters::<F>)
+    }
+}
+
+impl Plugin for PluginMachine {
+    fn build(&self, bevy_app: &mut App) {
+        bevy_app
+            .add_sub_tick_systems(MachineSystems)
+            .add_systems(SubTick(0), update_crafters.after(handle_crafter_extractors::<FilterSubTick<0>>));
+    }
+}
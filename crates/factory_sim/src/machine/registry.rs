@@ -0,0 +1,130 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use core::fmt::Debug;
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::{Entry, HashMap}};
+use nvm_str_id::SmolStr;
+
+use crate::item::{Item, ItemRegistry};
+
+use super::{Recipe, RecipeEntry};
+
+/// A [`Recipe`] referring to its items by name, resolved against the [`ItemRegistry`] on registration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeDefinition {
+    pub inputs:   Vec<(SmolStr, u32)>,
+    pub outputs:  Vec<(SmolStr, u32)>,
+    pub duration: u32,
+    pub power:    Option<u16>,
+}
+
+pub struct RecipeRegistration {
+    pub owner:  String,
+    pub recipe: Arc<Recipe>,
+}
+
+pub struct RecipeRegistryBuilder<'a> {
+    items:  &'a ItemRegistry,
+    lookup: HashMap<SmolStr, RecipeRegistration>,
+}
+
+pub enum RecipeRegistryBuilderError {
+    AlreadyRegistered(SmolStr, String),
+    UnknownItem(SmolStr, SmolStr),
+}
+
+impl Debug for RecipeRegistryBuilderError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::AlreadyRegistered(arg0, arg1) => {
+                write!(f, "RecipeRegistry: Recipe ({arg0}) is already registered by ({arg1})")
+            },
+            Self::UnknownItem(arg0, arg1) => {
+                write!(f, "RecipeRegistry: Recipe ({arg0}) refers to unregistered item ({arg1})")
+            },
+        }
+    }
+}
+
+impl<'a> RecipeRegistryBuilder<'a> {
+
+    #[must_use]
+    pub fn new(items: &'a ItemRegistry) -> Self {
+        Self {
+            items,
+            lookup: HashMap::default(),
+        }
+    }
+
+    /// Registers a recipe, as being registered by the given owner, and returns the resolved recipe.
+    pub fn register(&mut self, name: SmolStr, owner: String, definition: RecipeDefinition) -> Result<Arc<Recipe>, RecipeRegistryBuilderError> {
+        let resolve = |entries: &[(SmolStr, u32)]| -> Result<Vec<RecipeEntry>, RecipeRegistryBuilderError> {
+            entries.iter().map(|&(item, count)| {
+                let item = self.items.get(item).ok_or(RecipeRegistryBuilderError::UnknownItem(name, item))?.id;
+                Ok(RecipeEntry{ item, count })
+            }).collect()
+        };
+
+        let recipe = Recipe {
+            inputs:   resolve(&definition.inputs)?,
+            outputs:  resolve(&definition.outputs)?,
+            duration: definition.duration,
+            power:    definition.power,
+        };
+
+        match self.lookup.entry(name) {
+            Entry::Occupied(v) => {
+                Err(RecipeRegistryBuilderError::AlreadyRegistered(name, v.get().owner.clone()))
+            },
+            Entry::Vacant(v) => {
+                Ok(Arc::clone(&v.insert(RecipeRegistration{ owner, recipe: Arc::new(recipe) }).recipe))
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, name: SmolStr) -> Option<&RecipeRegistration> {
+        self.lookup.get(&name)
+    }
+
+    #[must_use]
+    pub fn build(self) -> RecipeRegistry {
+        let mut by_output: HashMap<Item, Vec<SmolStr>> = HashMap::default();
+        for (&name, registration) in &self.lookup {
+            for entry in &registration.recipe.outputs {
+                let recipes = by_output.entry(entry.item).or_default();
+                if !recipes.contains(&name) {
+                    recipes.push(name);
+                }
+            }
+        }
+
+        RecipeRegistry{
+            lookup: self.lookup,
+            by_output,
+        }
+    }
+
+}
+
+#[derive(Resource)]
+pub struct RecipeRegistry {
+    lookup:    HashMap<SmolStr, RecipeRegistration>,
+    by_output: HashMap<Item, Vec<SmolStr>>,
+}
+
+impl RecipeRegistry {
+
+    #[must_use]
+    pub fn get(&self, id: SmolStr) -> Option<&RecipeRegistration> {
+        self.lookup.get(&id)
+    }
+
+    /// The names of the recipes producing the given item.
+    #[must_use]
+    pub fn get_by_output(&self, item: Item) -> &[SmolStr] {
+        self.by_output.get(&item).map_or(&[], Vec::as_slice)
+    }
+
+}
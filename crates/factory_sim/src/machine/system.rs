@@ -0,0 +1,98 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::{ecs::query::QueryFilter, prelude::*};
+
+use crate::{
+    item::{ItemRegistry, ITEM_STACK_MAX},
+    power::{PowerSink, PowerSinkSupply, PowerStalled}, 
+    tick::{Cooldown, Tick}, 
+    track::{FilterReady, StackBuffer}
+};
+
+use super::{Crafter, CrafterExtractor, CrafterInserter};
+
+#[allow(clippy::missing_panics_doc)]
+pub fn handle_crafter_inserters<F: QueryFilter>(
+    mut q_inserters: Query<(Entity, &CrafterInserter, &mut StackBuffer), (FilterReady, F)>,
+    mut q_crafters: Query<&mut Crafter>,
+    mut commands: Commands,
+    tick: Res<Tick>,
+) {
+    for (id, inserter, mut src_buffer) in &mut q_inserters {
+        let Some(stack) = src_buffer.contents else {
+            continue;
+        };
+
+        let mut crafter = q_crafters.get_mut(inserter.target).unwrap();
+        if crafter.insert(stack).is_err() {
+            continue;
+        }
+
+        src_buffer.contents = None;
+        if inserter.cooldown > 0 {
+            commands.entity(id).insert(Cooldown::new(*tick, inserter.cooldown));
+        }
+    }
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn handle_crafter_extractors<F: QueryFilter>(
+    mut q_extractors: Query<(Entity, &CrafterExtractor, &mut StackBuffer), (FilterReady, F)>,
+    mut q_crafters: Query<&mut Crafter>,
+    mut commands: Commands,
+    tick: Res<Tick>,
+    registry: Option<Res<ItemRegistry>>,
+) {
+    for (id, extractor, mut dst_buffer) in &mut q_extractors {
+        if dst_buffer.contents.is_some() {
+            continue;
+        }
+
+        let mut crafter = q_crafters.get_mut(extractor.target).unwrap();
+        let max = match (&registry, crafter.outputs.get(extractor.slot)) {
+            (Some(registry), Some(slot)) => registry.info(slot.item).max_stack,
+            _ => ITEM_STACK_MAX,
+        };
+        dst_buffer.contents = crafter.extract_at_most(extractor.slot, max);
+        if dst_buffer.contents.is_some() && extractor.cooldown > 0 {
+            commands.entity(id).insert(Cooldown::new(*tick, extractor.cooldown));
+        }
+    }
+}
+
+/// A [`PowerSink`] along with the power it was supplied, if the crafter has one.
+pub type CrafterPower<'a> = Option<(&'a mut PowerSink, &'a PowerSinkSupply)>;
+
+pub fn update_crafters(mut q_crafters: Query<(&mut Crafter, CrafterPower, Has<PowerStalled>)>) {
+    for (mut crafter, mut power, is_stalled) in &mut q_crafters {
+        let crafter = &mut *crafter;
+
+        // The sink's throttle stalls it on a share of ticks when underpowered, so only unpowered crafters need checking
+        let is_powered = !is_stalled && (crafter.recipe.power.is_none() || power.as_ref().is_some_and(|(_, supply)| supply.amount > 0));
+        if is_powered {
+            if crafter.progress.is_none() && crafter.can_start() {
+                for (entry, slot) in crafter.recipe.inputs.iter().zip(&mut crafter.inputs) {
+                    slot.count -= entry.count;
+                }
+                crafter.progress = Some(0);
+            }
+
+            if let Some(progress) = &mut crafter.progress {
+                *progress += 1;
+                if *progress >= crafter.recipe.duration {
+                    for (entry, slot) in crafter.recipe.outputs.iter().zip(&mut crafter.outputs) {
+                        slot.count += entry.count;
+                    }
+                    crafter.progress = None;
+                }
+            }
+        }
+
+        if let (Some(recipe_power), Some((sink, _))) = (crafter.recipe.power, &mut power) {
+            let demand = if crafter.progress.is_some() || crafter.can_start() { recipe_power } else { 0 };
+            if sink.max != demand {
+                sink.max = demand;
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use std::sync::Arc;
+
+use nvm_str_id::SmolStr;
+
+use crate::{
+    test::create_app,
+    item::{Item, ItemRegistryBuilder, ItemStack, ITEM_STACK_MAX},
+    machine::{Crafter, CrafterExtractor, CrafterInserter, Recipe, RecipeDefinition, RecipeEntry, RecipeRegistryBuilder, RecipeRegistryBuilderError},
+    power::{PowerNetwork, PowerSink, PowerSinkSupply, PowerSource, PowerSourceDrain, PowerStalled},
+    tick::{TickPacer, TickRate},
+    track::{StackBuffer, TrackBuffer, TrackExtractor, TrackInserter, TrackQueue, TRACK_MAX_ITEMS}
+};
+
+fn create_recipe(power: Option<u16>) -> (Item, Item, Arc<Recipe>) {
+    let item_a = ItemStack::from_raw(1, 1).item();
+    let item_b = ItemStack::from_raw(2, 1).item();
+    let recipe = Recipe{
+        inputs:   vec![RecipeEntry{ item: item_a, count: 2 }],
+        outputs:  vec![RecipeEntry{ item: item_b, count: 1 }],
+        duration: 2,
+        power,
+    };
+    (item_a, item_b, Arc::new(recipe))
+}
+
+#[test]
+pub fn test_crafter_from_track_to_track() {
+    let (item_a, item_b, recipe) = create_recipe(None);
+
+    let mut app = create_app(TickPacer::unpaced());
+    let track_in = app.world.spawn((
+        TrackQueue::default().with(0).with(1),
+        {
+            let mut buffer = TrackBuffer::default();
+            buffer.push(item_a.as_stack(1)).unwrap();
+            buffer.push(item_a.as_stack(1)).unwrap();
+            buffer
+        },
+        TickRate(1),
+    )).id();
+    let track_out = app.world.spawn((TrackQueue::default(), TrackBuffer::default(), TickRate(1))).id();
+    let crafter   = app.world.spawn(Crafter::new(recipe)).id();
+
+    app.world.spawn((
+        TrackExtractor{ target: track_in, loc: 0, cooldown: 0 },
+        CrafterInserter{ target: crafter, cooldown: 0 },
+        StackBuffer{ contents: None },
+        TickRate(1),
+    ));
+    app.world.spawn((
+        CrafterExtractor{ target: crafter, slot: 0, cooldown: 0 },
+        TrackInserter{ target: track_out, loc: TRACK_MAX_ITEMS - 1, cooldown: 0 },
+        StackBuffer{ contents: None },
+        TickRate(1),
+    ));
+
+    // Both inputs moved into the crafter, crafting started
+    app.update();
+    app.update();
+    let state = app.world.get::<Crafter>(crafter).unwrap();
+    assert_eq!(0, state.inputs[0].count);
+    assert_eq!(Some(1), state.progress);
+
+    // Crafting completes
+    app.update();
+    let state = app.world.get::<Crafter>(crafter).unwrap();
+    assert_eq!(1, state.outputs[0].count);
+    assert_eq!(None, state.progress);
+
+    // Output moved to the track
+    app.update();
+    app.update();
+    assert_eq!(0, app.world.get::<Crafter>(crafter).unwrap().outputs[0].count);
+    assert_eq!(TrackQueue::default().with(TRACK_MAX_ITEMS - 1), *app.world.get::<TrackQueue>(track_out).unwrap());
+    assert_eq!(Some(item_b.as_stack(1)), app.world.get::<TrackBuffer>(track_out).unwrap().get(0));
+    assert!(app.world.get::<TrackBuffer>(track_in).unwrap().is_empty());
+}
+
+#[test]
+pub fn test_crafter_rejects_mismatched_items() {
+    let (item_a, item_b, recipe) = create_recipe(None);
+    let mut crafter = Crafter::new(recipe);
+
+    assert_eq!(Err(item_b.as_stack(1)), crafter.insert(item_b.as_stack(1)));
+    assert_eq!(Ok(()), crafter.insert(item_a.as_stack(ITEM_STACK_MAX as usize)));
+    assert_eq!(Ok(()), crafter.insert(item_a.as_stack(4)));
+    assert_eq!(Err(item_a.as_stack(1)), crafter.insert(item_a.as_stack(1)));
+    assert_eq!(None, crafter.extract(0));
+    assert_eq!(None, crafter.extract(1));
+}
+
+#[test]
+pub fn test_crafter_power() {
+    let (item_a, _, recipe) = create_recipe(Some(10));
+
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source  = app.world.spawn((PowerSource{ network, max: 0 }, PowerSourceDrain::default())).id();
+    let crafter = app.world.spawn((
+        {
+            let mut crafter = Crafter::new(recipe);
+            crafter.insert(item_a.as_stack(2)).unwrap();
+            crafter
+        },
+        PowerSink{ network, min: 0, max: 0 },
+        PowerSinkSupply::default(),
+    )).id();
+
+    // Unpowered, requests power but doesn't start or consume its inputs
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(10, app.world.get::<PowerSink>(crafter).unwrap().max);
+    assert_eq!(None, app.world.get::<Crafter>(crafter).unwrap().progress);
+    assert_eq!(2, app.world.get::<Crafter>(crafter).unwrap().inputs[0].count);
+
+    // Powered, completes and stops drawing power
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 10;
+    app.update();
+    app.update();
+    assert_eq!(None, app.world.get::<Crafter>(crafter).unwrap().progress);
+    assert_eq!(1, app.world.get::<Crafter>(crafter).unwrap().outputs[0].count);
+    assert_eq!(0, app.world.get::<PowerSink>(crafter).unwrap().max);
+
+    // Half powered, progresses every other tick once its demand is supplied
+    app.world.get_mut::<Crafter>(crafter).unwrap().insert(item_a.as_stack(2)).unwrap();
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 5;
+    for _ in 0..4 {
+        app.update();
+    }
+    assert_eq!(Some(1), app.world.get::<Crafter>(crafter).unwrap().progress);
+    app.update();
+    assert_eq!(2, app.world.get::<Crafter>(crafter).unwrap().outputs[0].count);
+
+    // Stops requesting power when it runs out of work, even while stalled
+    app.world.get_mut::<Crafter>(crafter).unwrap().insert(item_a.as_stack(2)).unwrap();
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 0;
+    app.update();
+    app.update();
+    assert!(app.world.get::<PowerStalled>(crafter).is_some());
+    assert_eq!(10, app.world.get::<PowerSink>(crafter).unwrap().max);
+    app.world.get_mut::<Crafter>(crafter).unwrap().inputs[0].count = 0;
+    app.update();
+    assert_eq!(0, app.world.get::<PowerSink>(crafter).unwrap().max);
+}
+
+#[test]
+pub fn test_recipe_registry() {
+    let mut items = ItemRegistryBuilder::default();
+    let item_a = items.register(SmolStr::new("ore"),   "base".to_owned()).unwrap();
+    let item_b = items.register(SmolStr::new("plate"), "base".to_owned()).unwrap();
+    let items  = items.build();
+
+    let definition = |input: &str, output: &str| RecipeDefinition{
+        inputs:   vec![(SmolStr::new(input),  2)],
+        outputs:  vec![(SmolStr::new(output), 1)],
+        duration: 4,
+        power:    Some(10),
+    };
+
+    let mut recipes = RecipeRegistryBuilder::new(&items);
+    let smelt = recipes.register(SmolStr::new("smelt"), "base".to_owned(), definition("ore", "plate")).unwrap();
+    assert_eq!(Recipe{
+        inputs:   vec![RecipeEntry{ item: item_a, count: 2 }],
+        outputs:  vec![RecipeEntry{ item: item_b, count: 1 }],
+        duration: 4,
+        power:    Some(10),
+    }, *smelt);
+
+    // Duplicates are rejected, reporting the original owner
+    assert!(matches!(
+        recipes.register(SmolStr::new("smelt"), "mod".to_owned(), definition("ore", "plate")),
+        Err(RecipeRegistryBuilderError::AlreadyRegistered(name, owner)) if name == SmolStr::new("smelt") && owner == "base"
+    ));
+
+    // Unknown items are rejected, in either inputs or outputs
+    assert!(matches!(
+        recipes.register(SmolStr::new("melt"), "mod".to_owned(), definition("gold", "plate")),
+        Err(RecipeRegistryBuilderError::UnknownItem(name, item)) if name == SmolStr::new("melt") && item == SmolStr::new("gold")
+    ));
+    assert!(matches!(
+        recipes.register(SmolStr::new("melt"), "mod".to_owned(), definition("ore", "gold")),
+        Err(RecipeRegistryBuilderError::UnknownItem(name, item)) if name == SmolStr::new("melt") && item == SmolStr::new("gold")
+    ));
+    assert!(recipes.get(SmolStr::new("melt")).is_none());
+
+    // Looked up by name and by output
+    let recipes = recipes.build();
+    let registration = recipes.get(SmolStr::new("smelt")).unwrap();
+    assert_eq!("base", registration.owner);
+    assert_eq!(smelt, registration.recipe);
+    assert!(recipes.get(SmolStr::new("melt")).is_none());
+    assert_eq!(&[SmolStr::new("smelt")], recipes.get_by_output(item_b));
+    assert!(recipes.get_by_output(item_a).is_empty());
+}
@@ -0,0 +1,319 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use core::fmt::Debug;
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use nvm_str_id::SmolStr;
+use ron::error::Position;
+use serde::Deserialize;
+
+use crate::{
+    item::{ItemInfo, ItemRegistryBuilder, ItemRegistryBuilderError}, 
+    machine::{RecipeDefinition, RecipeRegistryBuilder, RecipeRegistryBuilderError}
+};
+
+mod position;
+pub use position::*;
+
+#[cfg(test)]
+mod test;
+
+/// How a [`PackItem`] interacts with registrations from earlier packs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PackItemMode {
+    /// Registers a new item, conflicting with any earlier registration.
+    #[default]
+    Register,
+    /// Takes ownership of an earlier registration.
+    Replace,
+    /// Modifies an earlier registration, leaving its ownership unchanged.
+    Patch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackItem {
+    pub name: String,
+    #[serde(default)]
+    pub mode: PackItemMode,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub max_stack: Option<u16>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub sort_order: Option<i32>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl PackItem {
+
+    /// Applies this item's properties. Registrations and replacements overwrite the previous 
+    /// properties, whilst patches only overwrite the given properties and add to tags and metadata.
+    pub fn apply_info(&self, info: &mut ItemInfo) {
+        if self.mode != PackItemMode::Patch {
+            *info = ItemInfo{ 
+                display_name: self.name.clone(), 
+                ..ItemInfo::default() 
+            };
+        }
+
+        if let Some(display_name) = &self.display_name {
+            info.display_name.clone_from(display_name);
+        }
+        if let Some(max_stack) = self.max_stack {
+            info.max_stack = max_stack;
+        }
+        if let Some(sort_order) = self.sort_order {
+            info.sort_order = sort_order;
+        }
+        for tag in &self.tags {
+            info.add_tag(SmolStr::new(tag));
+        }
+        info.metadata.extend(self.metadata.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackRecipe {
+    pub name:     String,
+    pub inputs:   Vec<(String, u32)>,
+    pub outputs:  Vec<(String, u32)>,
+    pub duration: u32,
+    #[serde(default)]
+    pub power:    Option<u16>,
+}
+
+impl PackRecipe {
+
+    #[must_use]
+    pub fn to_definition(&self) -> RecipeDefinition {
+        let resolve = |entries: &[(String, u32)]| entries.iter().map(|(name, count)| (SmolStr::new(name), *count)).collect();
+        RecipeDefinition {
+            inputs:   resolve(&self.inputs),
+            outputs:  resolve(&self.outputs),
+            duration: self.duration,
+            power:    self.power,
+        }
+    }
+
+}
+
+/// The contents of a pack file, ie.
+/// ```ron
+/// (
+///     dependencies: ["base"],
+///     items: [
+///         (name: "iron_ore"),
+///         (name: "iron_plate", max_stack: 10, metadata: { "display": "Iron Plate" }),
+///         (name: "copper_plate", mode: Patch, max_stack: 5),
+///     ],
+///     recipes: [
+///         (name: "smelt_iron", inputs: [("iron_ore", 1)], outputs: [("iron_plate", 1)], duration: 60, power: 10),
+///     ],
+/// )
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackDefinition {
+    /// Owners of the packs that must be loaded before this one.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Owners of the packs that are loaded before this one, if present.
+    #[serde(default)]
+    pub after: Vec<String>,
+    #[serde(default)]
+    pub items: Vec<PackItem>,
+    #[serde(default)]
+    pub recipes: Vec<PackRecipe>,
+}
+
+/// An error loading or registering a pack. Each carries the pack's path and, 
+/// other than [`PackError::Io`], the position in its source the error relates to.
+pub enum PackError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    Item(PathBuf, Position, ItemRegistryBuilderError),
+    Recipe(PathBuf, Position, RecipeRegistryBuilderError),
+    MissingDependency(PathBuf, Position, String),
+    CyclicDependency(PathBuf, Position),
+}
+
+impl PackError {
+
+    #[must_use]
+    pub const fn position(&self) -> Option<Position> {
+        match self {
+            Self::Io(..) => None,
+            Self::Parse(_, err) => Some(err.position),
+            Self::Item(_, position, _) 
+                | Self::Recipe(_, position, _) 
+                | Self::MissingDependency(_, position, _) 
+                | Self::CyclicDependency(_, position) => Some(*position),
+        }
+    }
+
+}
+
+impl Debug for PackError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "Pack ({}): {err}", path.display()),
+            Self::Parse(path, err) => {
+                write!(f, "Pack ({}:{}): {}", path.display(), err.position, err.code)
+            },
+            Self::Item(path, position, err) => write!(f, "Pack ({}:{position}): {err:?}", path.display()),
+            Self::Recipe(path, position, err) => write!(f, "Pack ({}:{position}): {err:?}", path.display()),
+            Self::MissingDependency(path, position, owner) => write!(f, "Pack ({}:{position}): Depends on missing pack ({owner})", path.display()),
+            Self::CyclicDependency(path, position) => write!(f, "Pack ({}:{position}): Has cyclic dependencies", path.display()),
+        }
+    }
+}
+
+/// A [`PackDefinition`] loaded from a file, whose contents are registered as being owned by `owner`.
+#[derive(Debug)]
+pub struct Pack {
+    pub owner:      String,
+    pub path:       PathBuf,
+    pub definition: PackDefinition,
+    pub positions:  PackPositions,
+}
+
+impl Pack {
+
+    pub fn load(owner: String, path: impl Into<PathBuf>) -> Result<Self, PackError> {
+        let path = path.into();
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Self::parse(owner, path, &source),
+            Err(err)   => Err(PackError::Io(path, err)),
+        }
+    }
+
+    pub fn parse(owner: String, path: impl Into<PathBuf>, source: &str) -> Result<Self, PackError> {
+        let path = path.into();
+        let options = ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+        match options.from_str(source) {
+            Ok(definition) => Ok(Self{ owner, path, definition, positions: PackPositions::find(source) }),
+            Err(err)       => Err(PackError::Parse(path, err)),
+        }
+    }
+
+    /// Registers the pack's items, reporting every conflict rather than stopping at the first.
+    pub fn register_items(&self, builder: &mut ItemRegistryBuilder) -> Result<(), Vec<PackError>> {
+        let errors: Vec<_> = self.definition.items.iter().enumerate().filter_map(|(idx, item)| {
+            let (name, owner) = (SmolStr::new(&item.name), self.owner.clone());
+            let result = match item.mode {
+                PackItemMode::Register => builder.register(name, owner),
+                PackItemMode::Replace  => builder.replace(name, owner),
+                PackItemMode::Patch    => builder.patch(name, owner),
+            };
+            match result {
+                Ok(_) => {
+                    if let Some(info) = builder.info_mut(name) {
+                        item.apply_info(info);
+                    }
+                    None
+                },
+                Err(err) => Some(PackError::Item(self.path.clone(), position_of(&self.positions.items, idx), err)),
+            }
+        }).collect();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Registers the pack's recipes, reporting every conflict rather than stopping at the first.
+    pub fn register_recipes(&self, builder: &mut RecipeRegistryBuilder) -> Result<(), Vec<PackError>> {
+        let errors: Vec<_> = self.definition.recipes.iter().enumerate().filter_map(|(idx, recipe)| {
+            let result = builder.register(SmolStr::new(&recipe.name), self.owner.clone(), recipe.to_definition());
+            result.err().map(|err| PackError::Recipe(self.path.clone(), position_of(&self.positions.recipes, idx), err))
+        }).collect();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+}
+
+/// Loads packs and registers their contents. Packs are registered in the order they were 
+/// loaded, except where that would place a pack before one it depends on or is loaded after.
+#[derive(Default)]
+pub struct PackLoader {
+    packs: Vec<Pack>,
+}
+
+impl PackLoader {
+
+    pub fn load(&mut self, owner: String, path: impl AsRef<Path>) -> Result<&Pack, PackError> {
+        self.packs.push(Pack::load(owner, path.as_ref())?);
+        Ok(&self.packs[self.packs.len() - 1])
+    }
+
+    #[must_use]
+    pub fn packs(&self) -> &[Pack] {
+        &self.packs
+    }
+
+    /// The packs in the order they'll be registered.
+    pub fn ordered(&self) -> Result<Vec<&Pack>, Vec<PackError>> {
+        let mut errors = Vec::new();
+        let index_of = |owner: &str| self.packs.iter().position(|pack| pack.owner == owner);
+
+        let requires: Vec<Vec<usize>> = self.packs.iter().map(|pack| {
+            let mut requires = Vec::new();
+            for (dep_idx, owner) in pack.definition.dependencies.iter().enumerate() {
+                match index_of(owner) {
+                    Some(idx) => requires.push(idx),
+                    None => errors.push(PackError::MissingDependency(pack.path.clone(), position_of(&pack.positions.dependencies, dep_idx), owner.clone())),
+                }
+            }
+            requires.extend(pack.definition.after.iter().filter_map(|owner| index_of(owner)));
+            requires
+        }).collect();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut placed = vec![false; self.packs.len()];
+        let mut result = Vec::with_capacity(self.packs.len());
+        while result.len() < self.packs.len() {
+            let next = (0..self.packs.len()).find(|&idx| !placed[idx] && requires[idx].iter().all(|&dep| placed[dep]));
+            let Some(next) = next else {
+                return Err(self.packs.iter().zip(&placed).filter(|(_, placed)| !**placed).map(|(pack, _)| {
+                    // Points at the first dependency that couldn't be placed
+                    let unplaced = |owner: &String| index_of(owner).is_some_and(|idx| !placed[idx]);
+                    let position = pack.definition.dependencies.iter().position(unplaced)
+                        .map(|idx| position_of(&pack.positions.dependencies, idx))
+                        .or_else(|| pack.definition.after.iter().position(unplaced).map(|idx| position_of(&pack.positions.after, idx)))
+                        .unwrap_or(Position{ line: 1, col: 1 });
+                    PackError::CyclicDependency(pack.path.clone(), position)
+                }).collect());
+            };
+            placed[next] = true;
+            result.push(&self.packs[next]);
+        }
+        Ok(result)
+    }
+
+    pub fn register_items(&self, builder: &mut ItemRegistryBuilder) -> Result<(), Vec<PackError>> {
+        let mut errors = Vec::new();
+        for pack in self.ordered()? {
+            if let Err(mut pack_errors) = pack.register_items(builder) {
+                errors.append(&mut pack_errors);
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    pub fn register_recipes(&self, builder: &mut RecipeRegistryBuilder) -> Result<(), Vec<PackError>> {
+        let mut errors = Vec::new();
+        for pack in self.ordered()? {
+            if let Err(mut pack_errors) = pack.register_recipes(builder) {
+                errors.append(&mut pack_errors);
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use ron::error::Position;
+
+/// Where each entry of a [`PackDefinition`](super::PackDefinition)'s lists starts in its source.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackPositions {
+    pub dependencies: Vec<Position>,
+    pub after:        Vec<Position>,
+    pub items:        Vec<Position>,
+    pub recipes:      Vec<Position>,
+}
+
+impl PackPositions {
+
+    /// Scans the source of a pack that has already been parsed successfully, recording where each list entry starts.
+    #[must_use]
+    pub fn find(source: &str) -> Self {
+        let bytes = source.as_bytes();
+        let mut positions = Self::default();
+        let mut depth = 0_usize;
+        let mut key   = "";
+        let mut in_list     = false;
+        let mut expect_next = false;
+
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let start = idx;
+            let byte  = bytes[idx];
+            idx += 1;
+
+            match byte {
+                b'/' if bytes.get(idx) == Some(&b'/') => {
+                    idx = source[idx..].find('\n').map_or(bytes.len(), |v| idx + v);
+                    continue;
+                },
+                b'/' if bytes.get(idx) == Some(&b'*') => {
+                    idx = source[idx..].find("*/").map_or(bytes.len(), |v| idx + v + 2);
+                    continue;
+                },
+                _ if byte.is_ascii_whitespace() => continue,
+                _ => {},
+            }
+
+            if in_list && expect_next && depth == 2 && byte != b']' {
+                expect_next = false;
+                if let Some(list) = positions.list_mut(key) {
+                    list.push(position_at(source, start));
+                }
+            }
+
+            match byte {
+                b'"' | b'\'' => idx = skip_quoted(bytes, idx, byte),
+                b'(' | b'[' | b'{' => {
+                    if depth == 1 && byte == b'[' {
+                        in_list     = true;
+                        expect_next = true;
+                    }
+                    depth += 1;
+                },
+                b')' | b']' | b'}' => {
+                    depth = depth.saturating_sub(1);
+                    if depth <= 1 {
+                        in_list = false;
+                    }
+                },
+                b',' if depth == 2 => expect_next = true,
+                _ if byte.is_ascii_alphabetic() || byte == b'_' => {
+                    while bytes.get(idx).is_some_and(|v| v.is_ascii_alphanumeric() || *v == b'_') {
+                        idx += 1;
+                    }
+                    let ident = &source[start..idx];
+                    if ident == "r" && matches!(bytes.get(idx), Some(b'"' | b'#')) {
+                        idx = skip_raw(source, idx);
+                    } else if depth == 1 {
+                        key = ident;
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        positions
+    }
+
+    fn list_mut(&mut self, key: &str) -> Option<&mut Vec<Position>> {
+        match key {
+            "dependencies" => Some(&mut self.dependencies),
+            "after"        => Some(&mut self.after),
+            "items"        => Some(&mut self.items),
+            "recipes"      => Some(&mut self.recipes),
+            _ => None,
+        }
+    }
+
+}
+
+/// The entry's position, or the start of the source if it wasn't found.
+#[must_use]
+pub fn position_of(positions: &[Position], idx: usize) -> Position {
+    positions.get(idx).copied().unwrap_or(Position{ line: 1, col: 1 })
+}
+
+fn position_at(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |v| v + 1);
+    Position {
+        line: before.matches('\n').count() + 1,
+        col:  before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Skips past the closing quote of a string or char, starting after the opening quote.
+fn skip_quoted(bytes: &[u8], mut idx: usize, quote: u8) -> usize {
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' => idx += 2,
+            v if v == quote => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+    idx
+}
+
+/// Skips past the end of a raw string, starting after the `r`.
+fn skip_raw(source: &str, idx: usize) -> usize {
+    let hashes = source[idx..].bytes().take_while(|&v| v == b'#').count();
+    let body   = idx + hashes + 1;
+    let end    = format!("\"{}", "#".repeat(hashes));
+    source.get(body..).and_then(|rest| rest.find(&end)).map_or(source.len(), |v| body + v + end.len())
+}
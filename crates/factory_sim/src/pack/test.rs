@@ -0,0 +1,211 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use std::path::PathBuf;
+
+use nvm_str_id::SmolStr;
+
+use crate::{
+    item::{ItemProvenance, ItemProvenanceKind, ItemRegistryBuilder, ItemRegistryBuilderError, ITEM_STACK_MAX},
+    machine::{RecipeRegistryBuilder, RecipeRegistryBuilderError},
+    pack::{Pack, PackError, PackLoader}
+};
+
+const PACK_BASE: &str = r#"(
+    items: [
+        (name: "iron_ore"),
+        (name: "iron_plate", display_name: "Iron Plate", max_stack: 10, tags: ["metal"], metadata: { "colour": "grey" }),
+    ],
+    recipes: [
+        (name: "smelt_iron", inputs: [("iron_ore", 1)], outputs: [("iron_plate", 1)], duration: 60, power: 10),
+    ],
+)"#;
+
+const PACK_EXTRA: &str = r#"(
+    dependencies: ["base"],
+    items: [
+        (name: "iron_gear"),
+    ],
+    recipes: [
+        (name: "make_gear", inputs: [("iron_plate", 2)], outputs: [("iron_gear", 1)], duration: 30),
+    ],
+)"#;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let path = std::env::temp_dir().join(format!("nvm_factory_sim_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        for (file, contents) in files {
+            std::fs::write(path.join(file), contents).unwrap();
+        }
+        Self(path)
+    }
+
+    fn file(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+pub fn test_pack_layers() {
+    let dir = TempDir::new("layers", &[("base.ron", PACK_BASE), ("extra.ron", PACK_EXTRA)]);
+
+    let mut loader = PackLoader::default();
+    loader.load("base".to_owned(),  dir.file("base.ron" )).unwrap();
+    loader.load("extra".to_owned(), dir.file("extra.ron")).unwrap();
+    assert_eq!(Some(10), loader.packs()[0].definition.items[1].max_stack);
+    assert_eq!("grey", loader.packs()[0].definition.items[1].metadata["colour"]);
+
+    let mut items = ItemRegistryBuilder::default();
+    loader.register_items(&mut items).unwrap();
+    let items = items.build();
+    assert_eq!("base",  items.get(SmolStr::new("iron_plate")).unwrap().owner);
+    assert_eq!("extra", items.get(SmolStr::new("iron_gear" )).unwrap().owner);
+
+    let plate = items.info(items.get(SmolStr::new("iron_plate")).unwrap().id);
+    assert_eq!("Iron Plate", plate.display_name);
+    assert_eq!(10, plate.max_stack);
+    assert!(plate.has_tag(SmolStr::new("metal")));
+    let ore = items.info(items.get(SmolStr::new("iron_ore")).unwrap().id);
+    assert_eq!("iron_ore", ore.display_name);
+    assert_eq!(ITEM_STACK_MAX, ore.max_stack);
+
+    let mut recipes = RecipeRegistryBuilder::new(&items);
+    loader.register_recipes(&mut recipes).unwrap();
+    let recipes = recipes.build();
+    let gear = items.get(SmolStr::new("iron_gear")).unwrap().id;
+    assert_eq!(&[SmolStr::new("make_gear")], recipes.get_by_output(gear));
+    assert_eq!(Some(10), recipes.get(SmolStr::new("smelt_iron")).unwrap().recipe.power);
+}
+
+#[test]
+pub fn test_pack_errors() {
+    let dir = TempDir::new("errors", &[
+        ("base.ron",      PACK_BASE),
+        ("duplicate.ron", "(\n    items: [\n        (name: \"iron_ore\"),\n        (name: \"iron_plate\"),\n        (name: \"copper_ore\", mode: Patch),\n    ],\n)"),
+        ("unknown.ron",   "(\n    recipes: [\n        (name: \"fine\", inputs: [], outputs: [], duration: 1),\n        (name: \"bad\", inputs: [(\"copper_ore\", 1)], outputs: [], duration: 1),\n    ],\n)"),
+        ("invalid.ron",   "(\n    items: [\n        (nme: \"iron_ore\"),\n    ],\n)"),
+    ]);
+
+    let mut loader = PackLoader::default();
+    assert!(matches!(loader.load("missing".to_owned(), dir.file("missing.ron")), Err(PackError::Io(..))));
+    assert!(matches!(
+        loader.load("invalid".to_owned(), dir.file("invalid.ron")),
+        Err(PackError::Parse(path, err)) if path == dir.file("invalid.ron") && err.position.line == 3
+    ));
+
+    loader.load("base".to_owned(),      dir.file("base.ron"     )).unwrap();
+    loader.load("duplicate".to_owned(), dir.file("duplicate.ron")).unwrap();
+    loader.load("unknown".to_owned(),   dir.file("unknown.ron"  )).unwrap();
+
+    // Every conflict is reported
+    let mut items = ItemRegistryBuilder::default();
+    let errors = loader.register_items(&mut items).unwrap_err();
+    assert_eq!(3, errors.len());
+    assert!(errors.iter().all(|err| matches!(err, PackError::Item(path, _, _) if *path == dir.file("duplicate.ron"))));
+    assert!(matches!(&errors[0], PackError::Item(_, _, ItemRegistryBuilderError::AlreadyRegistered(_, _, owner)) if owner == "base"));
+    assert!(matches!(&errors[1], PackError::Item(_, _, ItemRegistryBuilderError::AlreadyRegistered(_, _, owner)) if owner == "base"));
+    assert!(matches!(&errors[2], PackError::Item(_, _, ItemRegistryBuilderError::NotRegistered(_, owner)) if owner == "duplicate"));
+    assert_eq!(
+        vec![Some((3, 9)), Some((4, 9)), Some((5, 9))], 
+        errors.iter().map(|err| err.position().map(|v| (v.line, v.col))).collect::<Vec<_>>()
+    );
+
+    let items = items.build();
+    let mut recipes = RecipeRegistryBuilder::new(&items);
+    let errors = loader.register_recipes(&mut recipes).unwrap_err();
+    assert!(matches!(
+        errors.as_slice(),
+        [PackError::Recipe(path, position, RecipeRegistryBuilderError::UnknownItem(..))] if *path == dir.file("unknown.ron") && position.line == 4
+    ));
+}
+
+#[test]
+pub fn test_pack_order() {
+    let dir = TempDir::new("order", &[
+        ("base.ron",  PACK_BASE),
+        ("extra.ron", PACK_EXTRA),
+        ("patch.ron", "(after: [\"extra\", \"other\"], items: [(name: \"iron_plate\", mode: Patch, max_stack: 20, tags: [\"sheet\"]), (name: \"iron_gear\", mode: Replace)])"),
+        ("cycle_a.ron", "(dependencies: [\"cycle_b\"])"),
+        ("cycle_b.ron", "(dependencies: [\"cycle_a\"])"),
+        ("missing.ron", "(dependencies: [\"other\"])"),
+    ]);
+
+    // Packs are reordered to satisfy their dependencies
+    let mut loader = PackLoader::default();
+    loader.load("patch".to_owned(), dir.file("patch.ron")).unwrap();
+    loader.load("extra".to_owned(), dir.file("extra.ron")).unwrap();
+    loader.load("base".to_owned(),  dir.file("base.ron" )).unwrap();
+    let order: Vec<_> = loader.ordered().unwrap().iter().map(|pack| pack.owner.as_str()).collect();
+    assert_eq!(vec!["base", "extra", "patch"], order);
+
+    let mut items = ItemRegistryBuilder::default();
+    loader.register_items(&mut items).unwrap();
+    let items = items.build();
+
+    let plate = items.get(SmolStr::new("iron_plate")).unwrap();
+    assert_eq!("base", plate.owner);
+    assert_eq!(vec![
+        ItemProvenance{ owner: "base".to_owned(),  kind: ItemProvenanceKind::Registered },
+        ItemProvenance{ owner: "patch".to_owned(), kind: ItemProvenanceKind::Patched    },
+    ], plate.provenance);
+
+    // Patches merge into the earlier properties, and stack sizes are clamped
+    let info = items.info(plate.id);
+    assert_eq!("Iron Plate", info.display_name);
+    assert_eq!(20.min(ITEM_STACK_MAX), info.max_stack);
+    assert_eq!(vec![SmolStr::new("metal"), SmolStr::new("sheet")], info.tags);
+
+    let gear = items.get(SmolStr::new("iron_gear")).unwrap();
+    assert_eq!("patch", gear.owner);
+    assert_eq!(vec![
+        ItemProvenance{ owner: "extra".to_owned(), kind: ItemProvenanceKind::Registered },
+        ItemProvenance{ owner: "patch".to_owned(), kind: ItemProvenanceKind::Replaced   },
+    ], gear.provenance);
+
+    // Unresolvable orders are reported
+    loader.load("cycle_a".to_owned(), dir.file("cycle_a.ron")).unwrap();
+    loader.load("cycle_b".to_owned(), dir.file("cycle_b.ron")).unwrap();
+    assert!(matches!(
+        loader.ordered().unwrap_err().as_slice(), 
+        [PackError::CyclicDependency(a, _), PackError::CyclicDependency(b, position)] if *a == dir.file("cycle_a.ron") && *b == dir.file("cycle_b.ron") && position.col == 17
+    ));
+
+    loader.load("missing".to_owned(), dir.file("missing.ron")).unwrap();
+    assert!(matches!(
+        loader.ordered().unwrap_err().as_slice(), 
+        [PackError::MissingDependency(path, position, owner)] if *path == dir.file("missing.ron") && owner == "other" && (position.line, position.col) == (1, 17)
+    ));
+}
+
+#[test]
+pub fn test_pack_positions() {
+    let source = r##"#![enable(implicit_some)]
+PackDefinition(
+    // items: [(name: "commented")],
+    after: ["base", /* "other", */ "extra"],
+    items: [
+        (name: "brackets [(", metadata: { "a": "]", "b": r#"")"# }),
+        PackItem(
+            name: "iron_gear",
+        ),
+    ],
+)"##;
+
+    let pack = Pack::parse("positions".to_owned(), "positions.ron", source).unwrap();
+    assert_eq!(2, pack.definition.items.len());
+    let lines = |positions: &[ron::error::Position]| positions.iter().map(|v| (v.line, v.col)).collect::<Vec<_>>();
+    assert_eq!(vec![(4, 13), (4, 36)], lines(&pack.positions.after));
+    assert_eq!(vec![(6, 9), (7, 9)],   lines(&pack.positions.items));
+    assert!(pack.positions.dependencies.is_empty());
+    assert!(pack.positions.recipes.is_empty());
+}
@@ -2,16 +2,41 @@
 
 use bevy::app::{PluginGroup, PluginGroupBuilder};
 
-use crate::{tick::{PluginTick, TickPacer}, track::PluginTrack};
+use crate::{machine::PluginMachine, power::PluginPower, tick::{PluginTick, TickPacer, SUB_TICK_DEFAULT}, track::PluginTrack};
 
 pub struct PluginsFactory {
     pub pacer: TickPacer,
+    /// See [`PluginTick::with_sub_ticks`].
+    pub sub_ticks: u8,
+}
+
+impl Default for PluginsFactory {
+    fn default() -> Self {
+        Self::new(TickPacer::unpaced())
+    }
+}
+
+impl PluginsFactory {
+
+    #[must_use]
+    pub const fn new(pacer: TickPacer) -> Self {
+        Self{ pacer, sub_ticks: SUB_TICK_DEFAULT }
+    }
+
+    /// See [`PluginTick::with_sub_ticks`].
+    #[must_use]
+    pub const fn with_sub_ticks(self, sub_ticks: u8) -> Self {
+        Self{ sub_ticks, ..self }
+    }
+
 }
 
 impl PluginGroup for PluginsFactory {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
-            .add(PluginTick::new(self.pacer))
+            .add(PluginTick::new(self.pacer).with_sub_ticks(self.sub_ticks))
             .add(PluginTrack)
+            .add(PluginPower)
+            .add(PluginMachine)
     }
 }
\ No newline at end of file
@@ -2,37 +2,190 @@
 
 use bevy::prelude::*;
 
+mod plugin;
+pub use plugin::*;
+
+mod system;
+pub use system::*;
+
+mod topology;
+pub use topology::*;
+
+mod statistics;
+pub use statistics::*;
+
+#[cfg(test)]
+mod test;
+
+/// The scale [`PowerNetwork::line_inefficiency`] is measured against, ie. a 
+/// loss of `POWER_LINE_LOSS_SCALE` consumes all power carried by the line.
+pub const POWER_LINE_LOSS_SCALE: u32 = 1000;
+
+/// The fraction of power lost over `length` units of line, out of [`POWER_LINE_LOSS_SCALE`].
+const fn line_loss(length: u32, line_inefficiency: u32) -> u32 {
+    let loss = length.saturating_mul(line_inefficiency);
+    if loss > POWER_LINE_LOSS_SCALE { POWER_LINE_LOSS_SCALE } else { loss }
+}
+
 // // //
 
+#[derive(Debug, Clone, Copy, Component)]
 pub struct PowerNetwork {
+    /// Power lost per unit of [`PowerLine::length`], as a fraction of [`POWER_LINE_LOSS_SCALE`].
+    pub line_inefficiency: u32,
+}
+
+/// Defaults for the networks spawned from the [`PowerPole`]/[`PowerLine`] graph.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct PowerSettings {
     pub line_inefficiency: u32,
+    /// When non-zero, spawned networks record [`PowerStatistics`] with this many ticks per minute.
+    /// When zero, the default, no statistics are recorded unless inserted on a network manually.
+    pub ticks_per_minute: u32,
 }
 
 // // //
 
+#[derive(Debug, Clone, Copy, Component)]
 pub struct PowerSource {
     pub network: Entity,
     pub max:     u32,
 }
 
+/// The amount drawn from a [`PowerSource`] during the last tick.
+#[derive(Debug, Default, Clone, Copy, Component)]
 pub struct PowerSourceDrain {
     pub amount: u32,
 }
 
 // // //
 
+#[derive(Debug, Clone, Copy, Component)]
 pub struct PowerSink {
     pub network: Entity,
     pub min:     u16,
     pub max:     u16,    
 }
 
+/// The length of line power travels to reach a [`PowerSink`], losses scale with its length.
+/// Kept in sync with the shortest path from its pole to a [`PowerSource`]'s pole for sinks with 
+/// a [`PowerConnection`], sinks without a path are lossless.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub struct PowerPath {
+    pub length: u32,
+}
+
+impl PowerPath {
+
+    /// The fraction of power lost over this path, out of [`POWER_LINE_LOSS_SCALE`].
+    #[must_use]
+    pub const fn loss(self, network: &PowerNetwork) -> u32 {
+        line_loss(self.length, network.line_inefficiency)
+    }
+
+}
+
+/// The amount delivered to a [`PowerSink`] during the last tick.
+#[derive(Debug, Default, Clone, Copy, Component)]
 pub struct PowerSinkSupply {
     pub amount: u16,
 }
 
 // // //
 
+/// Stores surplus power from a network's sources, and discharges it when they can't meet demand.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct PowerAccumulator {
+    pub network:        Entity,
+    pub capacity:       u32,
+    pub charge_rate:    u32,
+    pub discharge_rate: u32,
+}
+
+impl PowerAccumulator {
+
+    /// The power that can be discharged this tick.
+    #[must_use]
+    pub const fn reserve(self, charge: PowerAccumulatorCharge) -> u32 {
+        if charge.stored < self.discharge_rate { charge.stored } else { self.discharge_rate }
+    }
+
+    /// The power that can be charged this tick.
+    #[must_use]
+    pub const fn storable(self, charge: PowerAccumulatorCharge) -> u32 {
+        let remaining = self.capacity.saturating_sub(charge.stored);
+        if remaining < self.charge_rate { remaining } else { self.charge_rate }
+    }
+
+}
+
+/// The amount stored in a [`PowerAccumulator`].
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct PowerAccumulatorCharge {
+    pub stored: u32,
+}
+
+// // //
+
+/// The order a [`PowerSource`] is drained in, or a [`PowerSink`] is supplied in, lower tiers go first.
+/// Entities without a priority are in tier 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Component)]
+pub struct PowerPriority(pub u8);
+
+impl PowerPriority {
+
+    #[must_use]
+    pub fn tier_of(priority: Option<&Self>) -> u8 {
+        priority.map_or(0, |v| v.0)
+    }
+
+}
+
+// // //
+
+/// Tracks partial progress for a [`PowerSink`] that's powered between its minimum and maximum,
+/// such that it runs on a fraction of ticks in proportion to its supply. Inserted automatically.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct PowerThrottle {
+    pub progress: u32,
+}
+
+/// Marks a [`PowerSink`] that doesn't have enough power to run this tick.
+#[derive(Debug, Clone, Copy, Component)]
+#[component(storage = "SparseSet")]
+pub struct PowerStalled;
+
+// // //
+
+/// A node in the power graph, the pole's network is assigned automatically 
+/// from the [`PowerLine`]s connecting it to other poles.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct PowerPole {
+    pub network: Option<Entity>,
+    /// Length of the shortest path to a pole with a [`PowerSource`], or `0` if the network has none.
+    pub distance: u32,
+}
+
+/// An edge in the power graph connecting two [`PowerPole`]s.
+#[derive(Debug, Clone, Copy, Component)]
 pub struct PowerLine {
+    pub a:      Entity,
+    pub b:      Entity,
     pub length: u8,
 }
+
+impl PowerLine {
+
+    /// The fraction of power lost over this line, out of [`POWER_LINE_LOSS_SCALE`].
+    #[must_use]
+    pub const fn loss(self, network: &PowerNetwork) -> u32 {
+        line_loss(self.length as u32, network.line_inefficiency)
+    }
+
+}
+
+/// Connects a [`PowerSource`], [`PowerSink`] or [`PowerAccumulator`] to a [`PowerPole`], keeping its network in sync with the pole's.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct PowerConnection {
+    pub pole: Entity,
+}
@@ -0,0 +1,23 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use crate::tick::PowerTick;
+
+use super::{update_power_connections, update_power_networks, update_power_statistics, update_power_throttles, update_power_topology, PowerBudgets, PowerSettings};
+
+pub struct PluginPower;
+
+impl Plugin for PluginPower {
+    fn build(&self, bevy_app: &mut App) {
+        bevy_app
+            .init_resource::<PowerSettings>()
+            .init_resource::<PowerBudgets>()
+            .add_systems(PowerTick, (
+                update_power_topology,
+                update_power_connections,
+                update_power_networks, 
+                (update_power_throttles, update_power_statistics),
+            ).chain());
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use super::PowerBudgets;
+
+/// Power flowing through a network, summed over one or more ticks.
+/// Balances as `generated + discharged == consumed + lost + charged`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PowerSample {
+    /// Power drawn from sources.
+    pub generated:  u64,
+    /// Power drawn from accumulators.
+    pub discharged: u64,
+    /// Power delivered to sinks.
+    pub consumed:   u64,
+    /// Power lost over the network's lines.
+    pub lost:       u64,
+    /// Power stored in accumulators.
+    pub charged:    u64,
+    /// Power requested by sinks that couldn't be delivered.
+    pub unmet:      u64,
+}
+
+impl PowerSample {
+
+    pub fn accumulate(&mut self, other: &Self) {
+        self.generated  += other.generated;
+        self.discharged += other.discharged;
+        self.consumed   += other.consumed;
+        self.lost       += other.lost;
+        self.charged    += other.charged;
+        self.unmet      += other.unmet;
+    }
+
+}
+
+/// A ring buffer of the last `N` samples, each summed over `bucket` ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct PowerHistory<const N: usize> {
+    samples: [PowerSample; N],
+    head:    usize,
+    len:     usize,
+    bucket:  u32,
+    pending: PowerSample,
+    pending_ticks: u32,
+}
+
+impl<const N: usize> PowerHistory<N> {
+
+    #[must_use]
+    pub fn new(bucket: u32) -> Self {
+        Self {
+            samples: [PowerSample::default(); N],
+            head:    0,
+            len:     0,
+            bucket:  bucket.max(1),
+            pending: PowerSample::default(),
+            pending_ticks: 0,
+        }
+    }
+
+    /// Adds a tick's sample to the current bucket, completing it once `bucket` ticks have been added.
+    pub fn push(&mut self, sample: &PowerSample) {
+        self.pending.accumulate(sample);
+        self.pending_ticks += 1;
+        if self.pending_ticks < self.bucket {
+            return;
+        }
+
+        self.samples[self.head] = core::mem::take(&mut self.pending);
+        self.pending_ticks = 0;
+        self.head = (self.head + 1) % N;
+        self.len  = (self.len  + 1).min(N);
+    }
+
+    /// The most recently completed sample.
+    #[must_use]
+    pub fn latest(&self) -> Option<PowerSample> {
+        (self.len > 0).then(|| self.samples[(self.head + N - 1) % N])
+    }
+
+    /// The completed samples, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = PowerSample> + '_ {
+        (0..self.len).map(move |i| self.samples[(self.head + N - self.len + i) % N])
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub const fn bucket(&self) -> u32 {
+        self.bucket
+    }
+
+}
+
+/// Records a [`PowerNetwork`](super::PowerNetwork)'s power flow each tick, along with the history
+/// of the last 60 ticks, 60 minutes and 24 hours. Attached to networks spawned from the pole graph
+/// when [`PowerSettings::ticks_per_minute`](super::PowerSettings::ticks_per_minute) is non-zero, 
+/// or can be inserted on any network directly.
+#[derive(Debug, Clone, Component)]
+pub struct PowerStatistics {
+    pub last:    PowerSample,
+    pub ticks:   PowerHistory<60>,
+    pub minutes: PowerHistory<60>,
+    pub hours:   PowerHistory<24>,
+}
+
+impl PowerStatistics {
+
+    #[must_use]
+    pub fn new(ticks_per_minute: u32) -> Self {
+        Self {
+            last:    PowerSample::default(),
+            ticks:   PowerHistory::new(1),
+            minutes: PowerHistory::new(ticks_per_minute),
+            hours:   PowerHistory::new(ticks_per_minute.saturating_mul(60)),
+        }
+    }
+
+    /// The sum of the last `ticks` ticks, limited to the ticks recorded in `self.ticks`.
+    #[must_use]
+    pub fn recent(&self, ticks: usize) -> PowerSample {
+        let mut sample = PowerSample::default();
+        for tick in self.ticks.iter().skip(self.ticks.len().saturating_sub(ticks)) {
+            sample.accumulate(&tick);
+        }
+        sample
+    }
+
+    pub fn push(&mut self, sample: PowerSample) {
+        self.last = sample;
+        self.ticks.push(&sample);
+        self.minutes.push(&sample);
+        self.hours.push(&sample);
+    }
+
+}
+
+pub fn update_power_statistics(mut q_statistics: Query<(Entity, &mut PowerStatistics)>, budgets: Res<PowerBudgets>) {
+    for (id, mut statistics) in &mut q_statistics {
+        let sample = budgets.get(&id).map(|budget| PowerSample{
+            generated:  budget.drawn_from_sources() + budget.charged,
+            discharged: budget.drawn_from_reserve(),
+            consumed:   budget.delivered,
+            lost:       budget.drawn - budget.delivered,
+            charged:    budget.charged,
+            unmet:      budget.requested - budget.delivered,
+        }).unwrap_or_default();
+        statistics.push(sample);
+    }
+}
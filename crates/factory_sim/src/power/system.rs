@@ -0,0 +1,226 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{PowerAccumulator, PowerAccumulatorCharge, PowerNetwork, PowerPath, PowerPriority, PowerSink, PowerSinkSupply, PowerSource, PowerSourceDrain, PowerStalled, PowerThrottle, POWER_LINE_LOSS_SCALE};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PowerDemand {
+    /// Power the tier's sinks must draw to receive their minimum, including line losses.
+    pub min: u64,
+    /// Power the tier's sinks must draw to go from their minimum to maximum, including line losses.
+    pub extra: u64,
+    /// The portion of `min` that can be met.
+    pub allocated_min: u64,
+    /// The portion of `extra` that can be met.
+    pub allocated_extra: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PowerBudget {
+    /// Total power the network's sources can provide.
+    pub capacity: u64,
+    /// Total power the network's sources can provide, by [`PowerPriority`].
+    pub capacity_tiers: BTreeMap<u8, u64>,
+    /// Total power the network's accumulators can discharge.
+    pub reserve: u64,
+    /// Total power the network's accumulators can charge.
+    pub storable: u64,
+    /// Power the network's sinks must draw, by [`PowerPriority`].
+    pub demand_tiers: BTreeMap<u8, PowerDemand>,
+    /// Power requested by the network's sinks, excluding line losses.
+    pub requested: u64,
+    /// Power actually drawn from the network by sinks.
+    pub drawn: u64,
+    /// Power actually delivered to sinks, after line losses.
+    pub delivered: u64,
+    /// Power used to charge the network's accumulators.
+    pub charged: u64,
+}
+
+impl PowerBudget {
+
+    /// Allocates the network's capacity and reserve across the sink tiers, meeting each tier's 
+    /// minimum then maximum in order of priority.
+    pub fn allocate(&mut self) {
+        let mut remaining = self.capacity + self.reserve;
+        for demand in self.demand_tiers.values_mut() {
+            demand.allocated_min   = demand.min.min(remaining);
+            remaining -= demand.allocated_min;
+            demand.allocated_extra = demand.extra.min(remaining);
+            remaining -= demand.allocated_extra;
+        }
+    }
+
+    /// The portion of `drawn` provided by sources.
+    #[must_use]
+    pub fn drawn_from_sources(&self) -> u64 {
+        self.drawn.min(self.capacity)
+    }
+
+    /// The portion of `drawn` provided by accumulators.
+    #[must_use]
+    pub fn drawn_from_reserve(&self) -> u64 {
+        self.drawn - self.drawn_from_sources()
+    }
+
+}
+
+/// The [`PowerBudget`] of each network during the last tick.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+pub struct PowerBudgets(HashMap<Entity, PowerBudget>);
+
+/// The power a sink must draw to receive `amount` after losing `loss` over its path.
+const fn draw_for(amount: u16, loss: u32) -> u64 {
+    let delivered = (POWER_LINE_LOSS_SCALE - loss) as u64;
+    if delivered == 0 {
+        0
+    } else {
+        (amount as u64 * POWER_LINE_LOSS_SCALE as u64).div_ceil(delivered)
+    }
+}
+
+/// Scales `value` by `num/den`, where `num <= den`. Widened so large capacities can't overflow.
+const fn share_of(value: u64, num: u64, den: u64) -> u64 {
+    match (value as u128 * num as u128).checked_div(den as u128) {
+        Some(v) => v as u64,
+        None    => 0,
+    }
+}
+
+/// Distributes power within each network. Sources are drained in order of [`PowerPriority`], 
+/// followed by accumulators, sinks are supplied in order of [`PowerPriority`], and any surplus 
+/// from sources is used to charge accumulators.
+#[allow(clippy::missing_panics_doc)]
+pub fn update_power_networks(
+    q_networks: Query<&PowerNetwork>,
+    mut q_sources: Query<(&PowerSource, Option<&PowerPriority>, &mut PowerSourceDrain)>,
+    mut q_sinks: Query<(&PowerSink, Option<&PowerPath>, Option<&PowerPriority>, &mut PowerSinkSupply)>,
+    mut q_accumulators: Query<(&PowerAccumulator, &mut PowerAccumulatorCharge)>,
+    mut budgets: ResMut<PowerBudgets>,
+) {
+    budgets.clear();
+
+    for (source, priority, _) in &q_sources {
+        if q_networks.contains(source.network) {
+            let budget = budgets.entry(source.network).or_default();
+            budget.capacity += source.max as u64;
+            *budget.capacity_tiers.entry(PowerPriority::tier_of(priority)).or_default() += source.max as u64;
+        }
+    }
+
+    for (accumulator, charge) in &q_accumulators {
+        if q_networks.contains(accumulator.network) {
+            let budget = budgets.entry(accumulator.network).or_default();
+            budget.reserve  += accumulator.reserve(*charge) as u64;
+            budget.storable += accumulator.storable(*charge) as u64;
+        }
+    }
+
+    let sink_loss = |sink: &PowerSink, path: Option<&PowerPath>| -> Option<u32> {
+        let network = q_networks.get(sink.network).ok()?;
+        Some(path.map_or(0, |path| path.loss(network)))
+    };
+
+    for (sink, path, priority, _) in &q_sinks {
+        if let Some(loss) = sink_loss(sink, path) {
+            let budget = budgets.entry(sink.network).or_default();
+            budget.requested += sink.max.max(sink.min) as u64;
+            let demand = budget.demand_tiers.entry(PowerPriority::tier_of(priority)).or_default();
+            let draw_min = draw_for(sink.min, loss);
+            demand.min   += draw_min;
+            demand.extra += draw_for(sink.max.max(sink.min), loss) - draw_min;
+        }
+    }
+
+    for budget in budgets.values_mut() {
+        budget.allocate();
+    }
+
+    for (sink, path, priority, mut supply) in &mut q_sinks {
+        let (Some(loss), Some(budget)) = (sink_loss(sink, path), budgets.get_mut(&sink.network)) else {
+            supply.amount = 0;
+            continue;
+        };
+
+        let demand     = budget.demand_tiers[&PowerPriority::tier_of(priority)];
+        let draw_min   = draw_for(sink.min, loss);
+        let draw_extra = draw_for(sink.max.max(sink.min), loss) - draw_min;
+        let draw = share_of(draw_min,   demand.allocated_min,   demand.min  )
+                 + share_of(draw_extra, demand.allocated_extra, demand.extra);
+        budget.drawn += draw;
+
+        let delivered = share_of(draw, (POWER_LINE_LOSS_SCALE - loss) as u64, POWER_LINE_LOSS_SCALE as u64);
+        supply.amount = delivered.min(sink.max.max(sink.min) as u64) as u16;
+        budget.delivered += supply.amount as u64;
+    }
+
+    for budget in budgets.values_mut() {
+        budget.charged = (budget.capacity - budget.drawn_from_sources()).min(budget.storable);
+    }
+
+    for (source, priority, mut drain) in &mut q_sources {
+        drain.amount = budgets.get(&source.network).map_or(0, |budget| {
+            let tier = PowerPriority::tier_of(priority);
+            let used_by_higher: u64 = budget.capacity_tiers.range(..tier).map(|(_, &v)| v).sum();
+            let tier_capacity = budget.capacity_tiers[&tier];
+            let tier_used = (budget.drawn_from_sources() + budget.charged).saturating_sub(used_by_higher).min(tier_capacity);
+            share_of(source.max as u64, tier_used, tier_capacity) as u32
+        });
+    }
+
+    for (accumulator, mut charge) in &mut q_accumulators {
+        if let Some(budget) = budgets.get(&accumulator.network) {
+            let discharged = share_of(accumulator.reserve(*charge)  as u64, budget.drawn_from_reserve(), budget.reserve ) as u32;
+            let charged    = share_of(accumulator.storable(*charge) as u64, budget.charged,              budget.storable) as u32;
+            charge.stored  = charge.stored + charged - discharged;
+        }
+    }
+}
+
+/// Whether a sink runs this tick, carrying over progress from ticks supplied between its minimum and maximum.
+const fn advance_throttle(throttle: &mut PowerThrottle, sink: &PowerSink, supply: PowerSinkSupply) -> bool {
+    if supply.amount < sink.min {
+        throttle.progress = 0;
+        false
+    } else if supply.amount >= sink.max {
+        throttle.progress = 0;
+        true
+    } else {
+        throttle.progress += supply.amount as u32;
+        let is_running = throttle.progress >= sink.max as u32;
+        if is_running { throttle.progress -= sink.max as u32; }
+        is_running
+    }
+}
+
+/// A [`PowerSink`] along with the power it was supplied.
+pub type SuppliedPowerSink<'a> = (&'a PowerSink, &'a PowerSinkSupply);
+
+/// Stalls each [`PowerSink`] supplied less than its minimum, and on a fraction of ticks when
+/// supplied less than its maximum. Sinks without a [`PowerThrottle`] are given one.
+pub fn update_power_throttles(
+    mut q_sinks: Query<(Entity, SuppliedPowerSink, Option<&mut PowerThrottle>, Has<PowerStalled>)>,
+    mut commands: Commands,
+) {
+    for (id, (sink, supply), throttle, was_stalled) in &mut q_sinks {
+        let is_running = if let Some(mut throttle) = throttle {
+            advance_throttle(&mut throttle, sink, *supply)
+        } else {
+            let mut throttle = PowerThrottle::default();
+            let is_running = advance_throttle(&mut throttle, sink, *supply);
+            commands.entity(id).insert(throttle);
+            is_running
+        };
+
+        if is_running == was_stalled {
+            if is_running {
+                commands.entity(id).remove::<PowerStalled>();
+            } else {
+                commands.entity(id).insert(PowerStalled);
+            }
+        }
+    }
+}
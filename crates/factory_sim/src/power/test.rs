@@ -0,0 +1,330 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use crate::{
+    test::create_app,
+    tick::{TickPacer, TickRate},
+    track::TrackQueue,
+    power::{PowerAccumulator, PowerAccumulatorCharge, PowerConnection, PowerLine, PowerNetwork, PowerPath, PowerPole, PowerPriority, PowerSample, PowerSettings, PowerSink, PowerSinkSupply, PowerSource, PowerSourceDrain, PowerStatistics, PowerThrottle}
+};
+
+fn spawn_source(app: &mut App, network: Entity, max: u32) -> Entity {
+    app.world.spawn((PowerSource{network, max}, PowerSourceDrain::default())).id()
+}
+
+fn spawn_sink(app: &mut App, network: Entity, min: u16, max: u16) -> Entity {
+    app.world.spawn((PowerSink{network, min, max}, PowerSinkSupply::default())).id()
+}
+
+#[test]
+pub fn test_power_surplus() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source  = spawn_source(&mut app, network, 100);
+    let sink_1  = spawn_sink(&mut app, network, 20, 60);
+    let sink_2  = spawn_sink(&mut app, network, 20, 30);
+
+    app.update();
+
+    assert_eq!(30, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(60, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(90, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_shortage() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network  = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source_1 = spawn_source(&mut app, network, 50);
+    let source_2 = spawn_source(&mut app, network, 30);
+    let sink_1   = spawn_sink(&mut app, network, 20, 60);
+    let sink_2   = spawn_sink(&mut app, network, 20, 60);
+
+    // Minimums are met first, the remaining 40 is split across the rest of the demand
+    app.update();
+    assert_eq!(40, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(40, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(50, app.world.get::<PowerSourceDrain>(source_1).unwrap().amount);
+    assert_eq!(30, app.world.get::<PowerSourceDrain>(source_2).unwrap().amount);
+
+    // Brown-out, minimums are shared
+    app.world.despawn(source_1);
+    app.update();
+    assert_eq!(15, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(15, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(30, app.world.get::<PowerSourceDrain>(source_2).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_priority() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network  = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source_1 = spawn_source(&mut app, network, 40);
+    let source_2 = spawn_source(&mut app, network, 40);
+    let sink_1   = spawn_sink(&mut app, network, 10, 50);
+    let sink_2   = spawn_sink(&mut app, network, 10, 50);
+    app.world.entity_mut(source_1).insert(PowerPriority(1));
+    app.world.entity_mut(sink_2).insert(PowerPriority(1));
+
+    // Tier 0 sink is fully supplied before tier 1, tier 0 source is drained before tier 1
+    app.update();
+    assert_eq!(50, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(30, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(40, app.world.get::<PowerSourceDrain>(source_1).unwrap().amount);
+    assert_eq!(40, app.world.get::<PowerSourceDrain>(source_2).unwrap().amount);
+
+    app.world.get_mut::<PowerSink>(sink_2).unwrap().max = 10;
+    app.update();
+    assert_eq!(50, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(10, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(20, app.world.get::<PowerSourceDrain>(source_1).unwrap().amount);
+    assert_eq!(40, app.world.get::<PowerSourceDrain>(source_2).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_accumulator() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network     = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source      = spawn_source(&mut app, network, 50);
+    let sink        = spawn_sink(&mut app, network, 0, 30);
+    let accumulator = app.world.spawn((
+        PowerAccumulator{ network, capacity: 50, charge_rate: 15, discharge_rate: 25 },
+        PowerAccumulatorCharge::default(),
+    )).id();
+
+    // Charges from the surplus, limited by rate then capacity
+    for expected in [15, 30, 45, 50, 50] {
+        app.update();
+        assert_eq!(expected, app.world.get::<PowerAccumulatorCharge>(accumulator).unwrap().stored);
+        assert_eq!(30, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+    }
+    assert_eq!(30, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+
+    // Discharges only once the source is exhausted
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 10;
+    for expected in [30, 10, 0] {
+        app.update();
+        assert_eq!(expected, app.world.get::<PowerAccumulatorCharge>(accumulator).unwrap().stored);
+        assert_eq!(10, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+    }
+    assert_eq!(20, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+
+    app.update();
+    assert_eq!(10, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_large_capacity() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network     = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source_1    = spawn_source(&mut app, network, u32::MAX);
+    let source_2    = spawn_source(&mut app, network, u32::MAX);
+    let sink        = spawn_sink(&mut app, network, 0, 30);
+    let accumulator = app.world.spawn((
+        PowerAccumulator{ network, capacity: u32::MAX, charge_rate: u32::MAX, discharge_rate: u32::MAX },
+        PowerAccumulatorCharge::default(),
+    )).id();
+
+    // Sources share the draw, which is more than either could supply alone
+    app.update();
+    assert_eq!(30, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+    assert_eq!(u32::MAX, app.world.get::<PowerAccumulatorCharge>(accumulator).unwrap().stored);
+    assert_eq!(u32::MAX / 2 + 15, app.world.get::<PowerSourceDrain>(source_1).unwrap().amount);
+    assert_eq!(u32::MAX / 2 + 15, app.world.get::<PowerSourceDrain>(source_2).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_line_loss() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn(PowerNetwork{ line_inefficiency: 100 }).id();
+    let source  = spawn_source(&mut app, network, 100);
+    let sink_1  = spawn_sink(&mut app, network, 0, 45);
+    let sink_2  = spawn_sink(&mut app, network, 0, 45);
+    app.world.entity_mut(sink_2).insert(PowerPath{ length: 5 });
+
+    // Sink 2 loses half its draw over its path, so needs 90 to receive 45
+    app.update();
+    assert_eq!(33, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!(33, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(99, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+
+    // Path loses everything
+    app.world.entity_mut(sink_2).insert(PowerPath{ length: 10 });
+    app.update();
+    assert_eq!(45, app.world.get::<PowerSinkSupply>(sink_1).unwrap().amount);
+    assert_eq!( 0, app.world.get::<PowerSinkSupply>(sink_2).unwrap().amount);
+    assert_eq!(45, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_statistics() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn((
+        PowerNetwork{ line_inefficiency: 100 }, 
+        PowerStatistics::new(4),
+    )).id();
+    let source = spawn_source(&mut app, network, 100);
+    let sink   = spawn_sink(&mut app, network, 0, 150);
+    app.world.entity_mut(sink).insert(PowerPath{ length: 1 });
+    let accumulator = app.world.spawn((
+        PowerAccumulator{ network, capacity: 50, charge_rate: 10, discharge_rate: 10 },
+        PowerAccumulatorCharge{ stored: 50 },
+    )).id();
+
+    for _ in 0..10 {
+        app.update();
+    }
+
+    // Accumulator is empty after 5 ticks
+    let statistics = app.world.get::<PowerStatistics>(network).unwrap();
+    assert_eq!(PowerSample{
+        generated:  100,
+        discharged:   0,
+        consumed:    90,
+        lost:        10,
+        charged:      0,
+        unmet:       60,
+    }, statistics.last);
+
+    assert_eq!(10, statistics.ticks.len());
+    assert_eq!(2,  statistics.minutes.len());
+    assert_eq!(0,  statistics.hours.len());
+    assert_eq!(
+        vec![10, 10, 10, 10, 10, 0, 0, 0, 0, 0], 
+        statistics.ticks.iter().map(|v| v.discharged).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        (200, 20), 
+        (statistics.recent(2).generated, statistics.recent(7).discharged),
+    );
+    assert_eq!(
+        vec![(400, 40), (400, 10)], 
+        statistics.minutes.iter().map(|v| (v.generated, v.discharged)).collect::<Vec<_>>()
+    );
+    assert_eq!(0, app.world.get::<PowerAccumulatorCharge>(accumulator).unwrap().stored);
+    assert_eq!(100, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_topology() {
+    let mut app = create_app(TickPacer::unpaced());
+    app.world.insert_resource(PowerSettings{ line_inefficiency: 10, ticks_per_minute: 0 });
+
+    let pole_1 = app.world.spawn(PowerPole::default()).id();
+    let pole_2 = app.world.spawn(PowerPole::default()).id();
+    let pole_3 = app.world.spawn(PowerPole::default()).id();
+    let line_1 = app.world.spawn(PowerLine{ a: pole_1, b: pole_2, length: 2 }).id();
+    let line_2 = app.world.spawn(PowerLine{ a: pole_2, b: pole_3, length: 3 }).id();
+
+    let source = spawn_source(&mut app, Entity::PLACEHOLDER, 100);
+    let sink   = spawn_sink(&mut app, Entity::PLACEHOLDER, 10, 10);
+    let near   = spawn_sink(&mut app, Entity::PLACEHOLDER, 10, 10);
+    app.world.entity_mut(source).insert(PowerConnection{ pole: pole_1 });
+    app.world.entity_mut(sink  ).insert(PowerConnection{ pole: pole_3 });
+    app.world.entity_mut(near  ).insert(PowerConnection{ pole: pole_1 });
+
+    let network_of = |app: &App, pole: Entity| app.world.get::<PowerPole>(pole).unwrap().network.unwrap();
+    let network_count = |app: &mut App| app.world.query::<&PowerNetwork>().iter(&app.world).count();
+
+    // All poles share one network
+    app.update();
+    let network = network_of(&app, pole_1);
+    assert_eq!(network, network_of(&app, pole_2));
+    assert_eq!(network, network_of(&app, pole_3));
+    assert_eq!(network, app.world.get::<PowerSink>(sink).unwrap().network);
+    assert_eq!(PowerPath{ length: 5 }, *app.world.get::<PowerPath>(sink).unwrap());
+    assert_eq!(PowerPath{ length: 0 }, *app.world.get::<PowerPath>(near).unwrap());
+
+    // Only the far sink loses power over its path, drawing 11 to receive 10
+    app.update();
+    assert_eq!(10, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+    assert_eq!(10, app.world.get::<PowerSinkSupply>(near).unwrap().amount);
+    assert_eq!(21, app.world.get::<PowerSourceDrain>(source).unwrap().amount);
+    assert_eq!(1, network_count(&mut app));
+
+    // Removing a line splits the network
+    app.world.despawn(line_2);
+    app.update();
+    assert_eq!(network_of(&app, pole_1), network_of(&app, pole_2));
+    assert_ne!(network_of(&app, pole_1), network_of(&app, pole_3));
+    assert_eq!(0, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+    assert_eq!(2, network_count(&mut app));
+
+    // Adding it back merges them again
+    app.world.spawn(PowerLine{ a: pole_3, b: pole_2, length: 3 });
+    app.update();
+    assert_eq!(network_of(&app, pole_1), network_of(&app, pole_3));
+    assert_eq!(10, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+    assert_eq!(1, network_count(&mut app));
+
+    // Disconnecting the source leaves no distances to measure, then reconnecting it restores them
+    app.world.entity_mut(source).remove::<PowerConnection>();
+    app.update();
+    assert_eq!(0, app.world.get::<PowerPole>(pole_3).unwrap().distance);
+    assert_eq!(PowerPath{ length: 0 }, *app.world.get::<PowerPath>(sink).unwrap());
+    app.world.entity_mut(source).insert(PowerConnection{ pole: pole_1 });
+    app.update();
+    assert_eq!(PowerPath{ length: 5 }, *app.world.get::<PowerPath>(sink).unwrap());
+
+    // Removing all the poles cleans up the network
+    app.world.despawn(line_1);
+    app.world.despawn(pole_1);
+    app.world.despawn(pole_2);
+    app.world.despawn(pole_3);
+    app.update();
+    assert_eq!(0, network_count(&mut app));
+    assert_eq!(0, app.world.get::<PowerSinkSupply>(sink).unwrap().amount);
+}
+
+#[test]
+pub fn test_power_throttle() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source  = spawn_source(&mut app, network, 10);
+    let track   = spawn_sink(&mut app, network, 5, 20);
+    app.world.entity_mut(track).insert((TrackQueue::default().with(40), PowerThrottle::default(), TickRate(1)));
+
+    // Half power, advances every other tick
+    for i in 0..4_usize {
+        app.update();
+        assert_eq!((i, TrackQueue::default().with(40 - i.div_ceil(2))), (i, *app.world.get::<TrackQueue>(track).unwrap()));
+    }
+
+    // Full power, advances every tick
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 20;
+    for i in 0..4 {
+        app.update();
+        assert_eq!((i, TrackQueue::default().with(37 - i)), (i, *app.world.get::<TrackQueue>(track).unwrap()));
+    }
+
+    // Below minimum, stalls
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 4;
+    for i in 0..4 {
+        app.update();
+        assert_eq!((i, TrackQueue::default().with(34)), (i, *app.world.get::<TrackQueue>(track).unwrap()));
+    }
+}
+
+#[test]
+pub fn test_power_stall() {
+    let mut app = create_app(TickPacer::unpaced());
+    let network = app.world.spawn(PowerNetwork{ line_inefficiency: 0 }).id();
+    let source  = spawn_source(&mut app, network, 10);
+    let track   = spawn_sink(&mut app, network, 5, 20);
+    app.world.entity_mut(track).insert((TrackQueue::default().with(40), TickRate(1)));
+
+    // Without a throttle one is added, so it's still slowed on half power
+    for i in 0..4_usize {
+        app.update();
+        assert_eq!((i, TrackQueue::default().with(40 - i.div_ceil(2))), (i, *app.world.get::<TrackQueue>(track).unwrap()));
+    }
+    assert!(app.world.get::<PowerThrottle>(track).is_some());
+
+    // Below minimum, stalls
+    app.world.get_mut::<PowerSource>(source).unwrap().max = 4;
+    for i in 0..4 {
+        app.update();
+        assert_eq!((i, TrackQueue::default().with(38)), (i, *app.world.get::<TrackQueue>(track).unwrap()));
+    }
+}
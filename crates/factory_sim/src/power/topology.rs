@@ -0,0 +1,171 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use core::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
+
+use super::{PowerAccumulator, PowerConnection, PowerLine, PowerNetwork, PowerPath, PowerPole, PowerSettings, PowerSink, PowerSource, PowerStatistics};
+
+/// Changes to the graph that require the topology to be recalculated.
+type FilterTopologyChanged = Or<(Changed<PowerLine>, (Changed<PowerConnection>, With<PowerSource>))>;
+
+fn find_root(parents: &mut [usize], mut idx: usize) -> usize {
+    while parents[idx] != idx {
+        parents[idx] = parents[parents[idx]];
+        idx = parents[idx];
+    }
+    idx
+}
+
+/// The length of the shortest path from each pole to one of the `sources`, or `0` where there is none.
+fn find_distances(adjacent: &[Vec<(usize, u32)>], sources: impl IntoIterator<Item = usize>) -> Vec<u32> {
+    let mut distances = vec![u32::MAX; adjacent.len()];
+    let mut queue = BinaryHeap::new();
+    for idx in sources {
+        distances[idx] = 0;
+        queue.push(Reverse((0, idx)));
+    }
+
+    while let Some(Reverse((distance, idx))) = queue.pop() {
+        if distance > distances[idx] {
+            continue;
+        }
+
+        for &(next, length) in &adjacent[idx] {
+            let next_distance = distance.saturating_add(length);
+            if next_distance < distances[next] {
+                distances[next] = next_distance;
+                queue.push(Reverse((next_distance, next)));
+            }
+        }
+    }
+
+    for distance in &mut distances {
+        if *distance == u32::MAX {
+            *distance = 0;
+        }
+    }
+    distances
+}
+
+/// Recalculates the connected components of the [`PowerPole`]/[`PowerLine`] graph when it changes,
+/// spawning a [`PowerNetwork`] for each. Where a component contains poles from an existing network
+/// it inherits that network, networks that are no longer used are despawned. Each pole's distance
+/// to the nearest [`PowerSource`] is recalculated along with it.
+#[allow(clippy::too_many_arguments)]
+pub fn update_power_topology(
+    mut q_poles: Query<(Entity, &mut PowerPole)>,
+    q_lines: Query<&PowerLine>,
+    q_sources: Query<&PowerConnection, With<PowerSource>>,
+    mut q_networks: Query<&mut PowerNetwork>,
+    q_changed: Query<(), FilterTopologyChanged>,
+    mut removed_poles: RemovedComponents<PowerPole>,
+    mut removed_lines: RemovedComponents<PowerLine>,
+    mut removed_sources: RemovedComponents<PowerSource>,
+    mut removed_connections: RemovedComponents<PowerConnection>,
+    settings: Res<PowerSettings>,
+    mut managed: Local<HashSet<Entity>>,
+    mut commands: Commands,
+) {
+    let removed_poles   = removed_poles.read().count();
+    let removed_lines   = removed_lines.read().count();
+    let removed_sources = removed_sources.read().count();
+    let removed_connections = removed_connections.read().count();
+    let added_poles = q_poles.iter_mut().any(|(_, pole)| pole.is_added());
+    if !added_poles && q_changed.is_empty() && removed_poles == 0 && removed_lines == 0 && removed_sources == 0 && removed_connections == 0 && !settings.is_changed() {
+        return;
+    }
+
+    let lookup: HashMap<Entity, usize> = q_poles.iter().enumerate().map(|(idx, (id, _))| (id, idx)).collect();
+    let mut parents: Vec<usize> = (0..lookup.len()).collect();
+    for line in &q_lines {
+        if let (Some(&a), Some(&b)) = (lookup.get(&line.a), lookup.get(&line.b)) {
+            let (a, b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+            parents[a] = b;
+        }
+    }
+
+    let mut adjacent = vec![Vec::new(); parents.len()];
+    for line in &q_lines {
+        if let (Some(&a), Some(&b)) = (lookup.get(&line.a), lookup.get(&line.b)) {
+            adjacent[a].push((b, line.length as u32));
+            adjacent[b].push((a, line.length as u32));
+        }
+    }
+    let distances = find_distances(&adjacent, q_sources.iter().filter_map(|connection| lookup.get(&connection.pole).copied()));
+
+    let mut unclaimed = core::mem::take(&mut *managed);
+    let mut assigned: HashMap<usize, Entity> = HashMap::default();
+    for (idx, (_, mut pole)) in q_poles.iter_mut().enumerate() {
+        let root = find_root(&mut parents, idx);
+        let network = *assigned.entry(root).or_insert_with(|| {
+            let network = PowerNetwork{ line_inefficiency: settings.line_inefficiency };
+
+            match pole.network.filter(|id| unclaimed.remove(id)).and_then(|id| Some((id, q_networks.get_mut(id).ok()?))) {
+                Some((id, mut existing)) => {
+                    *existing = network;
+                    id
+                },
+                None if settings.ticks_per_minute > 0 => commands.spawn((network, PowerStatistics::new(settings.ticks_per_minute))).id(),
+                None => commands.spawn(network).id(),
+            }
+        });
+
+        if pole.network != Some(network) {
+            pole.network = Some(network);
+        }
+
+        if pole.distance != distances[idx] {
+            pole.distance = distances[idx];
+        }
+    }
+
+    managed.extend(assigned.values().copied());
+    for id in unclaimed {
+        if let Some(mut network) = commands.get_entity(id) {
+            network.despawn();
+        }
+    }
+}
+
+/// Points each [`PowerSource`], [`PowerSink`] and [`PowerAccumulator`] with a [`PowerConnection`] at its pole's network,
+/// and each such sink's [`PowerPath`] at its pole's distance from a source.
+pub fn update_power_connections(
+    q_poles: Query<&PowerPole>,
+    mut q_sources: Query<(&PowerConnection, &mut PowerSource)>,
+    mut q_sinks: Query<(Entity, &PowerConnection, &mut PowerSink, Option<&mut PowerPath>)>,
+    mut q_accumulators: Query<(&PowerConnection, &mut PowerAccumulator)>,
+    mut commands: Commands,
+) {
+    let network_of = |connection: &PowerConnection| {
+        q_poles.get(connection.pole).ok().and_then(|pole| pole.network).unwrap_or(Entity::PLACEHOLDER)
+    };
+
+    for (connection, mut source) in &mut q_sources {
+        let network = network_of(connection);
+        if source.network != network {
+            source.network = network;
+        }
+    }
+
+    for (id, connection, mut sink, path) in &mut q_sinks {
+        let network = network_of(connection);
+        if sink.network != network {
+            sink.network = network;
+        }
+
+        let length = q_poles.get(connection.pole).map_or(0, |pole| pole.distance);
+        match path {
+            Some(mut path) => { path.set_if_neq(PowerPath{ length }); },
+            None => { commands.entity(id).insert(PowerPath{ length }); },
+        }
+    }
+
+    for (connection, mut accumulator) in &mut q_accumulators {
+        let network = network_of(connection);
+        if accumulator.network != network {
+            accumulator.network = network;
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use crate::{plugin::PluginsFactory, tick::TickPacer};
+
+/// An app with the factory plugins, using the default number of sub-ticks.
+pub fn create_app(pacer: TickPacer) -> App {
+    let mut app = App::new();
+    app.add_plugins(PluginsFactory::new(pacer));
+    app
+}
@@ -0,0 +1,93 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+/// The largest scale [`TickMode::Running`] accepts, [`TickMode::Unbounded`] runs as fast as possible.
+pub const TICK_SCALE_MAX: f64 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickMode {
+    /// No ticks are run.
+    Paused,
+    /// Runs the given number of ticks as fast as the catch-up budget allows, then pauses.
+    Step(u32),
+    /// Runs ticks at the pacer's rate, scaled by the given factor. Unpaced pacers run a tick each frame at `1.0`.
+    Running(f64),
+    /// Runs the full catch-up budget of ticks every frame, regardless of the pacer.
+    Unbounded,
+}
+
+impl TickMode {
+
+    /// Clamps [`TickMode::Running`]'s scale to `0.0..=TICK_SCALE_MAX`, treating NaN as `0.0`.
+    #[must_use]
+    pub const fn sanitized(self) -> Self {
+        match self {
+            Self::Running(scale) if scale.is_nan() => Self::Running(0.0),
+            Self::Running(scale) => Self::Running(scale.clamp(0.0, TICK_SCALE_MAX)),
+            mode => mode,
+        }
+    }
+
+}
+
+/// Sent whenever the [`TickControl`] mode changes, including when a step completes and pauses.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct TickModeChanged {
+    pub from: TickMode,
+    pub to:   TickMode,
+}
+
+/// Controls how the tick scheduler runs ticks. Modes are [`TickMode::sanitized`] when set.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct TickControl {
+    mode: TickMode,
+    last: TickMode,
+}
+
+impl Default for TickControl {
+    fn default() -> Self {
+        Self::new(TickMode::Running(1.0))
+    }
+}
+
+impl TickControl {
+
+    #[must_use]
+    pub const fn new(mode: TickMode) -> Self {
+        let mode = mode.sanitized();
+        Self{ mode, last: mode }
+    }
+
+    #[must_use]
+    pub const fn mode(&self) -> TickMode {
+        self.mode
+    }
+
+    pub const fn set_mode(&mut self, mode: TickMode) {
+        self.mode = mode.sanitized();
+    }
+
+    /// Reports a change in mode since the last call.
+    pub fn take_change(&mut self) -> Option<TickModeChanged> {
+        (self.mode != self.last).then(|| {
+            let change = TickModeChanged{ from: self.last, to: self.mode };
+            self.last = self.mode;
+            change
+        })
+    }
+
+    /// Records ticks run in [`TickMode::Step`], pausing once all steps have been run.
+    pub const fn consume_steps(&mut self, ticks: u32) {
+        if let TickMode::Step(steps) = self.mode {
+            let remaining = steps.saturating_sub(ticks);
+            if remaining == 0 {
+                self.mode = TickMode::Paused;
+            } else {
+                self.mode = TickMode::Step(remaining);
+                self.last = self.mode;
+            }
+        }
+    }
+
+}
@@ -0,0 +1,22 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use super::run_tick;
+
+/// Runs the simulation directly on a [`World`], without an [`App`] update loop or [`Time`].
+/// The world is expected to have been set up by [`crate::plugin::PluginsFactory`].
+pub struct FactorySim;
+
+impl FactorySim {
+
+    /// Runs exactly `ticks` ticks, ignoring the [`super::TickPacer`] and [`super::TickControl`].
+    /// Change trackers are cleared after each tick, as an [`App`] would after each update.
+    pub fn run_ticks(world: &mut World, ticks: u32) {
+        for _ in 0..ticks {
+            run_tick(world);
+            world.clear_trackers();
+        }
+    }
+
+}
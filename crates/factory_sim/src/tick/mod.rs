@@ -17,6 +17,15 @@ pub use cooldown::*;
 mod system;
 pub use system::*;
 
+mod control;
+pub use control::*;
+
+mod headless;
+pub use headless::*;
+
+#[cfg(test)]
+mod test;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Resource)]
 pub struct Tick(u32);
 
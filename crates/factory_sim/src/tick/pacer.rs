@@ -2,10 +2,15 @@
 
 use bevy::prelude::*;
 
+/// The most ticks run in one frame to catch up, unless configured otherwise.
+pub const TICK_CATCH_UP_DEFAULT: u32 = 5;
+
 #[derive(Debug, Clone, Copy, Resource)]
 pub struct TickPacer {
     accum: f64,
     frame: f64,
+    catch_up: u32,
+    dropped:  u64,
 }
 
 impl TickPacer {
@@ -14,12 +19,20 @@ impl TickPacer {
         Self {
             accum: 0.0,
             frame: if per_second <= 0.0 { 0.0 } else { 1.0/per_second },
+            catch_up: TICK_CATCH_UP_DEFAULT,
+            dropped:  0,
         }
     }
 
     #[must_use]
     pub const fn unpaced() -> Self {
-        Self { accum: 0.0, frame: 0.0 }
+        Self { accum: 0.0, frame: 0.0, catch_up: TICK_CATCH_UP_DEFAULT, dropped: 0 }
+    }
+
+    /// Sets the most ticks run in one frame, any further elapsed ticks are dropped.
+    #[must_use]
+    pub const fn with_catch_up(self, max: u32) -> Self {
+        Self { catch_up: if max == 0 { 1 } else { max }, ..self }
     }
 }
 
@@ -35,9 +48,41 @@ impl TickPacer {
         frames
     }
 
+    /// The ticks to run this frame, elapsed ticks over the catch-up budget are dropped.
+    #[must_use]
+    pub fn update_with_catch_up(&mut self, delta: f64) -> u32 {
+        let ticks = self.update(delta);
+        let run   = ticks.min(self.catch_up);
+        self.dropped += u64::from(ticks - run);
+        run
+    }
+
+    /// The ticks to run this frame for an unpaced pacer running at the given scale, a tick per frame at `1.0`.
+    /// Fractional ticks carry over to later frames, and ticks over the catch-up budget are dropped.
+    #[must_use]
+    pub fn update_unpaced(&mut self, scale: f64) -> u32 {
+        self.accum += scale;
+        let ticks = self.accum.floor() as u32;
+        self.accum -= f64::from(ticks);
+        let run = ticks.min(self.catch_up);
+        self.dropped += u64::from(ticks - run);
+        run
+    }
+
     #[must_use]
     pub fn is_paced(&self) -> bool {
         self.frame > 0.0
     }
 
+    #[must_use]
+    pub const fn catch_up(&self) -> u32 {
+        self.catch_up
+    }
+
+    /// The total ticks dropped for exceeding the catch-up budget.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
 }
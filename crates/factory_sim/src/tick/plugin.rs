@@ -2,13 +2,16 @@
 
 use bevy::prelude::*;
 
-use super::{tick_scheduler, update_cooldowns, PreTick, SubTick1, SubTick2, SubTick3, SubTick4, Tick, TickPacer};
+use super::{tick_scheduler, update_cooldowns, update_tick_rates, PowerTick, PreTick, SubTick, SubTickCount, Tick, TickControl, TickModeChanged, TickPacer, SUB_TICK_DEFAULT};
 
-pub struct PluginTick(TickPacer);
+pub struct PluginTick {
+    pacer:     TickPacer,
+    sub_ticks: SubTickCount,
+}
 
 impl Default for PluginTick {
     fn default() -> Self {
-        Self(TickPacer::unpaced())
+        Self::new(TickPacer::unpaced())
     }
 }
 
@@ -16,7 +19,13 @@ impl PluginTick {
 
     #[must_use]
     pub const fn new(pacer: TickPacer) -> Self {
-        Self(pacer)
+        Self{ pacer, sub_ticks: SubTickCount::new(SUB_TICK_DEFAULT) }
+    }
+
+    /// Divides each tick into the given number of sub-ticks, clamped to `1..=SUB_TICK_MAX`.
+    #[must_use]
+    pub const fn with_sub_ticks(self, count: u8) -> Self {
+        Self{ sub_ticks: SubTickCount::new(count), ..self }
     }
 
 }
@@ -24,14 +33,20 @@ impl PluginTick {
 impl Plugin for PluginTick {
     fn build(&self, bevy_app: &mut bevy::prelude::App) {
         bevy_app
-            .insert_resource(self.0)
+            .insert_resource(self.pacer)
+            .insert_resource(self.sub_ticks)
             .insert_resource(Tick::new(0))
+            .init_resource::<TickControl>()
+            .add_event::<TickModeChanged>()
             .add_schedule(Schedule::new(PreTick))
-            .add_schedule(Schedule::new(SubTick1))
-            .add_schedule(Schedule::new(SubTick2))
-            .add_schedule(Schedule::new(SubTick3))
-            .add_schedule(Schedule::new(SubTick4))
+            .add_schedule(Schedule::new(PowerTick));
+
+        for sub_tick in 0..self.sub_ticks.get() {
+            bevy_app.add_schedule(Schedule::new(SubTick(sub_tick)));
+        }
+
+        bevy_app
             .add_systems(Update, tick_scheduler)
-            .add_systems(PreTick, update_cooldowns);
+            .add_systems(PreTick, (update_cooldowns, update_tick_rates));
     }
-}
\ No newline at end of file
+}
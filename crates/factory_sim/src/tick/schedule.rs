@@ -1,56 +1,191 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use core::cmp::Ordering;
 
-use super::{Tick, TickPacer};
+use bevy::{ecs::{query::QueryFilter, schedule::{ScheduleLabel, SystemConfigs}, system::EntityCommands}, prelude::*};
+
+use super::{Tick, TickControl, TickMode, TickPacer};
+
+/// The most [`SubTick`]s a tick can be divided into.
+pub const SUB_TICK_MAX: u8 = 8;
+
+/// The number of [`SubTick`]s a tick is divided into, unless configured otherwise.
+pub const SUB_TICK_DEFAULT: u8 = 4;
 
 #[allow(clippy::missing_panics_doc)]
 pub fn tick_scheduler(world: &mut World) {
-    let pacer = world.get_resource::<TickPacer>().unwrap();
+    let mode = {
+        let mut control = world.get_resource_mut::<TickControl>().unwrap();
+        let change = control.take_change();
+        let mode   = control.mode();
+        if let Some(change) = change {
+            world.send_event(change);
+        }
+        mode
+    };
 
-    if pacer.is_paced() {
-        let ticks = {
+    let pacer = world.get_resource::<TickPacer>().unwrap();
+    let ticks = match mode {
+        TickMode::Paused       => 0,
+        TickMode::Step(steps)  => steps.min(pacer.catch_up()),
+        TickMode::Unbounded    => pacer.catch_up(),
+        TickMode::Running(scale) if !pacer.is_paced() => {
+            world.get_resource_mut::<TickPacer>().unwrap().update_unpaced(scale)
+        },
+        TickMode::Running(scale) => {
             let delta = world.get_resource::<Time>().unwrap().delta_seconds_f64();
             let mut pacer = world.get_resource_mut::<TickPacer>().unwrap();
-            pacer.update(delta)
-        };
-    
-        if ticks == 0 {
-            return;
-        }
+            pacer.update_with_catch_up(delta * scale)
+        },
+    };
+
+    for _ in 0..ticks {
+        run_tick(world);
+    }
+
+    let mut control = world.get_resource_mut::<TickControl>().unwrap();
+    control.consume_steps(ticks);
+    if let Some(change) = control.take_change() {
+        world.send_event(change);
     }
+}
 
+/// Runs a single tick, through each of its schedules.
+#[allow(clippy::missing_panics_doc)]
+pub fn run_tick(world: &mut World) {
     world.get_resource_mut::<Tick>().unwrap().advance().unwrap();
     world.run_schedule(PreTick);
-    world.run_schedule(SubTick1);
-    world.run_schedule(SubTick2);
-    world.run_schedule(SubTick3);
-    world.run_schedule(SubTick4);
+    world.run_schedule(PowerTick);
+    for sub_tick in 0..world.get_resource::<SubTickCount>().unwrap().get() {
+        world.run_schedule(SubTick(sub_tick));
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
 pub struct PreTick;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
-pub struct SubTick1;
+pub struct PowerTick;
 
+/// Run in order, from `0` to [`SubTickCount`], after [`PowerTick`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
-pub struct SubTick2;
+pub struct SubTick(pub u8);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
-pub struct SubTick3;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct SubTickCount(u8);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ScheduleLabel)]
-pub struct SubTick4;
+impl SubTickCount {
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
-pub struct TickRate1;
+    /// Clamped to `1..=SUB_TICK_MAX`.
+    #[must_use]
+    pub const fn new(count: u8) -> Self {
+        Self(if count == 0 { 1 } else if count > SUB_TICK_MAX { SUB_TICK_MAX } else { count })
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
-pub struct TickRate2;
+    #[must_use]
+    pub const fn get(self) -> u8 {
+        self.0
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
-pub struct TickRate3;
+}
 
+/// The number of sub-ticks, from the first, an entity is updated in each tick.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
-pub struct TickRate4;
+pub struct TickRate(pub u8);
+
+/// Marks entities updated in sub-tick `K`, kept in sync with their [`TickRate`] during [`PreTick`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct InSubTick<const K: u8>;
+
+pub type FilterSubTick<const K: u8> = With<InSubTick<K>>;
+
+/// Systems that are instantiated once for each sub-tick, filtered to the entities updated in it.
+pub trait SubTickSystems {
+    fn build<F: QueryFilter + 'static>(&self, sub_tick: SubTick) -> SystemConfigs;
+}
+
+pub trait AppSubTickExt {
+    /// Adds the systems to each [`SubTick`], the tick plugin must be added first.
+    fn add_sub_tick_systems(&mut self, systems: impl SubTickSystems) -> &mut Self;
+}
+
+macro_rules! for_each_sub_tick {
+    ($sub_tick:expr, $k:ident => $body:expr) => {
+        match $sub_tick {
+            0 => { const $k: u8 = 0; $body },
+            1 => { const $k: u8 = 1; $body },
+            2 => { const $k: u8 = 2; $body },
+            3 => { const $k: u8 = 3; $body },
+            4 => { const $k: u8 = 4; $body },
+            5 => { const $k: u8 = 5; $body },
+            6 => { const $k: u8 = 6; $body },
+            7 => { const $k: u8 = 7; $body },
+            _ => unreachable!("Sub-tick exceeds SUB_TICK_MAX"),
+        }
+    };
+}
+
+impl AppSubTickExt for App {
+    fn add_sub_tick_systems(&mut self, systems: impl SubTickSystems) -> &mut Self {
+        let count = self.world.get_resource::<SubTickCount>().map_or(SUB_TICK_DEFAULT, |count| count.get());
+        for sub_tick in 0..count {
+            let configs = for_each_sub_tick!(sub_tick, K => systems.build::<FilterSubTick<K>>(SubTick(K)));
+            self.add_systems(SubTick(sub_tick), configs);
+        }
+        self
+    }
+}
+
+/// The number of [`InSubTick`] markers the entity has, from the first.
+fn sub_tick_markers(entity: &EntityRef) -> u8 {
+    (0..SUB_TICK_MAX).find(|&sub_tick| !for_each_sub_tick!(sub_tick, K => entity.contains::<InSubTick<K>>())).unwrap_or(SUB_TICK_MAX)
+}
+
+/// Inserts the markers for sub-ticks `0..rate` as a single bundle.
+fn insert_sub_tick_markers(entity: &mut EntityCommands, rate: u8) {
+    match rate {
+        0 => {},
+        1 => { entity.insert(InSubTick::<0>); },
+        2 => { entity.insert((InSubTick::<0>, InSubTick::<1>)); },
+        3 => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>)); },
+        4 => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>, InSubTick::<3>)); },
+        5 => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>, InSubTick::<3>, InSubTick::<4>)); },
+        6 => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>, InSubTick::<3>, InSubTick::<4>, InSubTick::<5>)); },
+        7 => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>, InSubTick::<3>, InSubTick::<4>, InSubTick::<5>, InSubTick::<6>)); },
+        _ => { entity.insert((InSubTick::<0>, InSubTick::<1>, InSubTick::<2>, InSubTick::<3>, InSubTick::<4>, InSubTick::<5>, InSubTick::<6>, InSubTick::<7>)); },
+    }
+}
+
+/// Removes the markers for sub-ticks `rate..SUB_TICK_MAX` as a single bundle.
+fn remove_sub_tick_markers(entity: &mut EntityCommands, rate: u8) {
+    match rate {
+        0 => { entity.remove::<(InSubTick<0>, InSubTick<1>, InSubTick<2>, InSubTick<3>, InSubTick<4>, InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        1 => { entity.remove::<(InSubTick<1>, InSubTick<2>, InSubTick<3>, InSubTick<4>, InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        2 => { entity.remove::<(InSubTick<2>, InSubTick<3>, InSubTick<4>, InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        3 => { entity.remove::<(InSubTick<3>, InSubTick<4>, InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        4 => { entity.remove::<(InSubTick<4>, InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        5 => { entity.remove::<(InSubTick<5>, InSubTick<6>, InSubTick<7>)>(); },
+        6 => { entity.remove::<(InSubTick<6>, InSubTick<7>)>(); },
+        7 => { entity.remove::<InSubTick<7>>(); },
+        _ => {},
+    }
+}
+
+/// Syncs the [`InSubTick`] markers with each changed [`TickRate`]. Only the markers that differ
+/// are inserted or removed, in a single bundle, so an entity moves archetype at most once.
+pub fn update_tick_rates(q_rates: Query<(Entity, &TickRate, EntityRef), Changed<TickRate>>, mut removed: RemovedComponents<TickRate>, mut commands: Commands) {
+    for id in removed.read() {
+        if let Some(mut entity) = commands.get_entity(id) {
+            remove_sub_tick_markers(&mut entity, 0);
+        }
+    }
+
+    for (id, rate, entity) in &q_rates {
+        let (from, to) = (sub_tick_markers(&entity), rate.0.min(SUB_TICK_MAX));
+        match from.cmp(&to) {
+            Ordering::Less    => insert_sub_tick_markers(&mut commands.entity(id), to),
+            Ordering::Greater => remove_sub_tick_markers(&mut commands.entity(id), to),
+            Ordering::Equal   => {},
+        }
+    }
+}
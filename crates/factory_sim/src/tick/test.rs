@@ -0,0 +1,178 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use core::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{test, tick::{Cooldown, FactorySim, InSubTick, PreTick, SubTick, Tick, TickControl, TickMode, TickModeChanged, TickPacer, TickRate, SUB_TICK_DEFAULT, TICK_SCALE_MAX}};
+
+fn create_app(pacer: TickPacer) -> App {
+    let mut app = test::create_app(pacer);
+    app.insert_resource(Time::<()>::default());
+    app
+}
+
+fn update_by(app: &mut App, seconds: f64) {
+    app.world.resource_mut::<Time>().advance_by(Duration::from_secs_f64(seconds));
+    app.update();
+}
+
+#[test]
+pub fn test_catch_up() {
+    let mut app = create_app(TickPacer::paced(8.0).with_catch_up(3));
+
+    // Slow frames run multiple ticks, up to the budget
+    update_by(&mut app, 0.3125);
+    assert_eq!(Tick::new(2), *app.world.resource::<Tick>());
+    update_by(&mut app, 0.0625);
+    assert_eq!(Tick::new(3), *app.world.resource::<Tick>());
+    assert_eq!(0, app.world.resource::<TickPacer>().dropped());
+
+    // Beyond that they're dropped
+    update_by(&mut app, 1.25);
+    assert_eq!(Tick::new(6), *app.world.resource::<Tick>());
+    assert_eq!(7, app.world.resource::<TickPacer>().dropped());
+
+    // Fast frames wait
+    update_by(&mut app, 0.0625);
+    assert_eq!(Tick::new(6), *app.world.resource::<Tick>());
+}
+
+#[test]
+pub fn test_tick_control() {
+    let mut app = create_app(TickPacer::paced(8.0).with_catch_up(4));
+    let changes = |app: &mut App| app.world.resource_mut::<Events<TickModeChanged>>().drain().collect::<Vec<_>>();
+
+    // Paused doesn't run ticks, or build up ticks to catch up on
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Paused);
+    update_by(&mut app, 1.0);
+    assert_eq!(Tick::new(0), *app.world.resource::<Tick>());
+    assert_eq!(vec![TickModeChanged{ from: TickMode::Running(1.0), to: TickMode::Paused }], changes(&mut app));
+
+    // Steps run regardless of time, within the catch-up budget, then pause
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Step(6));
+    update_by(&mut app, 0.0);
+    assert_eq!(Tick::new(4), *app.world.resource::<Tick>());
+    assert_eq!(TickMode::Step(2), app.world.resource::<TickControl>().mode());
+    update_by(&mut app, 0.0);
+    assert_eq!(Tick::new(6), *app.world.resource::<Tick>());
+    assert_eq!(vec![
+        TickModeChanged{ from: TickMode::Paused,  to: TickMode::Step(6) },
+        TickModeChanged{ from: TickMode::Step(2), to: TickMode::Paused  },
+    ], changes(&mut app));
+    update_by(&mut app, 0.0);
+    assert_eq!(Tick::new(6), *app.world.resource::<Tick>());
+
+    // Running is scaled
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(2.0));
+    update_by(&mut app, 0.25);
+    assert_eq!(Tick::new(10), *app.world.resource::<Tick>());
+
+    // Unbounded runs the full budget
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Unbounded);
+    update_by(&mut app, 0.0);
+    assert_eq!(Tick::new(14), *app.world.resource::<Tick>());
+    assert_eq!(2, changes(&mut app).len());
+}
+
+#[test]
+pub fn test_headless() {
+    #[derive(Default, Resource)]
+    struct Runs(u32, u32);
+
+    let mut app = test::create_app(TickPacer::paced(8.0));
+    app.init_resource::<Runs>()
+        .add_systems(PreTick, |mut runs: ResMut<Runs>| runs.0 += 1)
+        .add_systems(SubTick(SUB_TICK_DEFAULT - 1), |mut runs: ResMut<Runs>| runs.1 += 1);
+
+    // No `Time` is needed, and the pacer is ignored
+    FactorySim::run_ticks(&mut app.world, 1000);
+    assert_eq!(Tick::new(1000), *app.world.resource::<Tick>());
+
+    let runs = app.world.resource::<Runs>();
+    assert_eq!((1000, 1000), (runs.0, runs.1));
+}
+
+#[test]
+pub fn test_headless_trackers() {
+    let mut app = test::create_app(TickPacer::unpaced());
+    app.add_systems(SubTick(0), |q: Query<Entity, Without<Cooldown>>, tick: Res<Tick>, mut commands: Commands| {
+        for id in &q {
+            commands.entity(id).insert(Cooldown::new(*tick, 1));
+        }
+    });
+    app.world.spawn_empty();
+
+    // Cooldowns are repeatedly added and removed, without the removals building up
+    FactorySim::run_ticks(&mut app.world, 1000);
+    assert!(app.world.removed::<Cooldown>().count() <= 1);
+}
+
+#[test]
+pub fn test_tick_rate_markers() {
+    let mut app = create_app(TickPacer::unpaced());
+    let entity = app.world.spawn(TickRate(3)).id();
+    let markers = |app: &App| {
+        let entity = app.world.entity(entity);
+        [
+            entity.contains::<InSubTick<0>>(), entity.contains::<InSubTick<1>>(), 
+            entity.contains::<InSubTick<2>>(), entity.contains::<InSubTick<3>>(),
+        ]
+    };
+
+    app.update();
+    assert_eq!([true, true, true, false], markers(&app));
+
+    app.world.entity_mut(entity).insert(TickRate(1));
+    app.update();
+    assert_eq!([true, false, false, false], markers(&app));
+
+    app.world.entity_mut(entity).insert(TickRate(4));
+    app.update();
+    assert_eq!([true, true, true, true], markers(&app));
+
+    app.world.entity_mut(entity).insert(TickRate(0));
+    app.update();
+    assert_eq!([false, false, false, false], markers(&app));
+
+    app.world.entity_mut(entity).insert(TickRate(2));
+    app.update();
+    app.world.entity_mut(entity).remove::<TickRate>();
+    app.update();
+    assert_eq!([false, false, false, false], markers(&app));
+}
+
+#[test]
+pub fn test_tick_control_scale() {
+    let mut app = create_app(TickPacer::paced(8.0));
+
+    // Zero scale doesn't run ticks
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(0.0));
+    update_by(&mut app, 1.0);
+    assert_eq!(Tick::new(0), *app.world.resource::<Tick>());
+
+    // Invalid scales are clamped rather than corrupting the pacer
+    for (scale, expected) in [(-2.0, 0.0), (f64::NAN, 0.0), (f64::INFINITY, TICK_SCALE_MAX)] {
+        app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(scale));
+        assert_eq!(TickMode::Running(expected), app.world.resource::<TickControl>().mode());
+    }
+
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(-2.0));
+    update_by(&mut app, 1.0);
+    assert_eq!(Tick::new(0), *app.world.resource::<Tick>());
+
+    // Recovers once the scale is restored
+    app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(1.0));
+    update_by(&mut app, 0.25);
+    assert_eq!(Tick::new(2), *app.world.resource::<Tick>());
+
+    // Unpaced pacers scale the tick per frame, carrying over fractions
+    let mut app = create_app(TickPacer::unpaced());
+    for (scale, expected) in [(0.0, [0, 0, 0, 0]), (0.5, [0, 1, 1, 2]), (2.0, [4, 6, 8, 10])] {
+        app.world.resource_mut::<TickControl>().set_mode(TickMode::Running(scale));
+        for expected in expected {
+            app.update();
+            assert_eq!((scale, Tick::new(expected)), (scale, *app.world.resource::<Tick>()));
+        }
+    }
+}
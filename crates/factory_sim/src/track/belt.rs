@@ -0,0 +1,85 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use super::{TrackBuffer, TrackPassthrough, TrackQueue};
+
+/// A lane of a [`TrackBelt`], in the same order as the belt's track lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackLane {
+    Left,
+    Right,
+}
+
+impl TrackLane {
+
+    pub const ALL: [TrackLane; 2] = [TrackLane::Left, TrackLane::Right];
+
+    #[must_use]
+    pub const fn index(self) -> usize {
+        match self {
+            Self::Left  => 0,
+            Self::Right => 1,
+        }
+    }
+
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Left  => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+}
+
+/// A two lane belt. Each lane is its own track entity, so lanes advance and block independently.
+#[derive(Debug, Clone, Copy, Component, PartialEq, Eq)]
+pub struct TrackBelt {
+    pub lanes: [Entity; 2],
+}
+
+/// Links a lane's track entity back to its [`TrackBelt`].
+#[derive(Debug, Clone, Copy, Component, PartialEq, Eq)]
+pub struct TrackBeltLane {
+    pub belt: Entity,
+    pub lane: TrackLane,
+}
+
+impl TrackBelt {
+
+    /// Spawns a belt and its empty lanes, with the given bundle added to each lane (eg. its tick rate).
+    pub fn spawn(world: &mut World, lane: impl Bundle + Clone) -> Entity {
+        let belt  = world.spawn_empty().id();
+        let lanes = TrackLane::ALL.map(|l| world.spawn((
+            TrackQueue::default(), 
+            TrackBuffer::default(), 
+            TrackBeltLane{ belt, lane: l }, 
+            lane.clone()
+        )).id());
+        world.entity_mut(belt).insert(Self{ lanes });
+        belt
+    }
+
+    #[must_use]
+    pub const fn lane(&self, lane: TrackLane) -> Entity {
+        self.lanes[lane.index()]
+    }
+
+    /// A connection inserting onto the given lane of this belt, after `into`.
+    #[must_use]
+    pub const fn side_load(&self, lane: TrackLane, into: usize) -> TrackPassthrough {
+        TrackPassthrough::new(self.lane(lane), into)
+    }
+
+    /// Connections from the end of each of this belt's lanes, onto the start of the same lane on `dst`.
+    /// Each is to be inserted on the source lane's entity.
+    #[must_use]
+    pub const fn connect_to(&self, dst: &Self) -> [(Entity, TrackPassthrough); 2] {
+        [
+            (self.lanes[0], TrackPassthrough::new_end_to_end(dst.lanes[0])),
+            (self.lanes[1], TrackPassthrough::new_end_to_end(dst.lanes[1])),
+        ]
+    }
+
+}
@@ -0,0 +1,102 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use crate::item::{Item, ItemFilter, ItemRegistry};
+
+use super::TrackPassthrough;
+
+/// How a junction picks between its connections, when more than one can transfer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrackJunctionMode {
+    /// Cycles through the connections, starting after the last one used.
+    #[default]
+    RoundRobin,
+    /// Uses the first connection that can transfer.
+    Priority,
+}
+
+/// Picks a connection, in `mode` order starting from `next`, and advances `next` past it.
+pub(super) fn select_connection(mode: TrackJunctionMode, next: &mut usize, len: usize, mut can_use: impl FnMut(usize) -> bool) -> Option<usize> {
+    let start = match mode {
+        TrackJunctionMode::RoundRobin => *next,
+        TrackJunctionMode::Priority   => 0,
+    };
+    let idx = (0..len).map(|i| (start + i) % len).find(|&i| can_use(i))?;
+    *next = (idx + 1) % len;
+    Some(idx)
+}
+
+/// Moves items from the head of this track into one of several tracks.
+#[derive(Debug, Clone, Component, PartialEq, Eq)]
+pub struct TrackSplitter {
+    pub outputs: Vec<TrackPassthrough>,
+    pub mode:    TrackJunctionMode,
+    pub next:    usize,
+}
+
+impl TrackSplitter {
+
+    #[must_use]
+    pub fn new(outputs: impl IntoIterator<Item = TrackPassthrough>, mode: TrackJunctionMode) -> Self {
+        Self{ outputs: outputs.into_iter().collect(), mode, next: 0 }
+    }
+
+}
+
+/// Moves items from the head of several tracks into this track, at `loc`.
+#[derive(Debug, Clone, Component, PartialEq, Eq)]
+pub struct TrackMerger {
+    /// The input tracks, in priority order for [`TrackJunctionMode::Priority`].
+    pub inputs: Vec<Entity>,
+    pub loc:    u8,
+    pub mode:   TrackJunctionMode,
+    pub next:   usize,
+}
+
+impl TrackMerger {
+
+    #[must_use]
+    pub fn new(inputs: impl IntoIterator<Item = Entity>, into: usize, mode: TrackJunctionMode) -> Self {
+        Self{ inputs: inputs.into_iter().collect(), loc: (into + 1) as u8, mode, next: 0 }
+    }
+
+    /// Creates a merger that inserts at the end of this track.
+    #[must_use]
+    pub fn new_end_to_end(inputs: impl IntoIterator<Item = Entity>, mode: TrackJunctionMode) -> Self {
+        Self::new(inputs, super::TRACK_MAX_ITEMS - 1, mode)
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackRoute {
+    pub filter: ItemFilter,
+    pub output: TrackPassthrough,
+}
+
+/// Moves items from the head of this track into the first route whose filter matches the item.
+/// Items that match no route, or whose route is blocked, are sent to the fallback instead.
+#[derive(Debug, Clone, Component, PartialEq, Eq)]
+pub struct TrackRouter {
+    pub routes:   Vec<TrackRoute>,
+    pub fallback: Option<TrackPassthrough>,
+}
+
+impl TrackRouter {
+
+    #[must_use]
+    pub fn new(routes: impl IntoIterator<Item = (ItemFilter, TrackPassthrough)>, fallback: Option<TrackPassthrough>) -> Self {
+        Self{ 
+            routes: routes.into_iter().map(|(filter, output)| TrackRoute{ filter, output }).collect(), 
+            fallback,
+        }
+    }
+
+    /// The preferred output for the item, if any.
+    #[must_use]
+    pub fn route(&self, item: Item, registry: Option<&ItemRegistry>) -> Option<&TrackPassthrough> {
+        self.routes.iter().find(|route| route.filter.matches(item, registry)).map(|route| &route.output)
+    }
+
+}
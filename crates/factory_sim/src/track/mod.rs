@@ -11,6 +11,18 @@ pub use buffer::*;
 mod shift;
 pub use shift::*;
 
+mod junction;
+pub use junction::*;
+
+mod belt;
+pub use belt::*;
+
+mod segments;
+pub use segments::*;
+
+mod speed;
+pub use speed::*;
+
 mod system;
 pub use system::*;
 
@@ -1,44 +1,34 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
-use bevy::prelude::*;
+use bevy::{ecs::{query::QueryFilter, schedule::SystemConfigs}, prelude::*};
 
-use crate::tick::{SubTick1, SubTick2, SubTick3, SubTick4, TickRate1, TickRate2, TickRate3, TickRate4};
+use crate::tick::{update_tick_rates, AppSubTickExt, PreTick, SubTick, SubTickSystems};
 
-use super::system::{advance_conveyors, handle_track_passthrough, handle_track_stack_extractors, handle_track_stack_inserters};
+use super::{speed::{consume_track_steps, update_track_speeds}, system::{advance_conveyors, handle_track_mergers, handle_track_passthrough, handle_track_routers, handle_track_splitters, handle_track_stack_extractors, handle_track_stack_inserters}};
 
 pub struct PluginTrack;
 
-pub type FilterSubTick1 = Or<(With<TickRate4>, With<TickRate3>, With<TickRate2>, With<TickRate1>)>;
-pub type FilterSubTick2 = Or<(With<TickRate4>, With<TickRate3>, With<TickRate2>)>;
-pub type FilterSubTick3 = Or<(With<TickRate4>, With<TickRate3>)>;
-pub type FilterSubTick4 = With<TickRate4>;
+struct TrackSystems;
+
+impl SubTickSystems for TrackSystems {
+    fn build<F: QueryFilter + 'static>(&self, _sub_tick: SubTick) -> SystemConfigs {
+        (
+            handle_track_stack_extractors::<F>,
+            handle_track_passthrough::<F>,
+            handle_track_splitters::<F>,
+            handle_track_routers::<F>,
+            handle_track_mergers::<F>,
+            advance_conveyors::<F>,
+            handle_track_stack_inserters::<F>,
+            consume_track_steps::<F>,
+        ).chain()
+    }
+}
 
 impl Plugin for PluginTrack {
     fn build(&self, bevy_app: &mut App) {
         bevy_app
-            .add_systems(SubTick1, (
-                handle_track_stack_extractors::<FilterSubTick1>,
-                handle_track_passthrough::<FilterSubTick1>,
-                advance_conveyors::<FilterSubTick1>,
-                handle_track_stack_inserters::<FilterSubTick1>,
-            ).chain())
-            .add_systems(SubTick2, (
-                handle_track_stack_extractors::<FilterSubTick2>,
-                handle_track_passthrough::<FilterSubTick2>,
-                advance_conveyors::<FilterSubTick2>,
-                handle_track_stack_inserters::<FilterSubTick2>,
-            ).chain())
-            .add_systems(SubTick3, (
-                handle_track_stack_extractors::<FilterSubTick3>,
-                handle_track_passthrough::<FilterSubTick3>,
-                advance_conveyors::<FilterSubTick3>,
-                handle_track_stack_inserters::<FilterSubTick3>,
-            ).chain())
-            .add_systems(SubTick4, (
-                handle_track_stack_extractors::<FilterSubTick4>,
-                handle_track_passthrough::<FilterSubTick4>,
-                advance_conveyors::<FilterSubTick4>,
-                handle_track_stack_inserters::<FilterSubTick4>,
-            ).chain());
+            .add_systems(PreTick, update_track_speeds.before(update_tick_rates))
+            .add_sub_tick_systems(TrackSystems);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,82 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::prelude::*;
+
+use super::{TrackBuffer, TrackPassthrough, TrackQueue, TRACK_MAX_ITEMS};
+
+/// A track of arbitrary length, made of chained [`TRACK_MAX_ITEMS`] segments. 
+/// Positions run from `0` at the exit to `len - 1` at the entry, as with a single track.
+/// 
+/// The entry segment is always full length, so connections end-to-end into it or inserters at its 
+/// raw locations see the track's full length. Any remainder is taken up by the segment after it, 
+/// tracks shorter than [`TRACK_MAX_ITEMS`] are a single segment, and must be connected with [`TrackSegments::passthrough_into`].
+#[derive(Debug, Clone, Component, PartialEq, Eq)]
+pub struct TrackSegments {
+    /// The segments, from the exit to the entry.
+    pub segments: Vec<Entity>,
+    pub len:      usize,
+}
+
+impl TrackSegments {
+
+    /// Spawns a track and its empty segments, with the given bundle added to each segment (eg. its tick rate).
+    /// Each segment passes its items onto the next, towards the exit.
+    /// 
+    /// # Panics
+    /// - If len is 0
+    pub fn spawn(world: &mut World, len: usize, segment: impl Bundle + Clone) -> Entity {
+        assert!(len > 0, "Track must have a length");
+
+        let segments: Vec<_> = (0..len.div_ceil(TRACK_MAX_ITEMS)).map(|_| world.spawn((
+            TrackQueue::default(), 
+            TrackBuffer::default(), 
+            segment.clone()
+        )).id()).collect();
+        let track = Self{ segments, len };
+
+        for idx in 1..track.segments.len() {
+            let (dst, loc) = track.locate(track.segment_start(idx) - 1).unwrap();
+            world.entity_mut(track.segments[idx]).insert(TrackPassthrough::new(dst, loc));
+        }
+
+        world.spawn(track).id()
+    }
+
+    #[must_use]
+    pub fn exit(&self) -> Entity {
+        self.segments[0]
+    }
+
+    #[must_use]
+    pub fn entry(&self) -> Entity {
+        self.segments[self.segments.len() - 1]
+    }
+
+    /// The position on the track of the given segment's location `0`.
+    const fn segment_start(&self, idx: usize) -> usize {
+        if idx > 0 && idx == self.segments.len() - 1 {
+            self.len - TRACK_MAX_ITEMS
+        } else {
+            idx * TRACK_MAX_ITEMS
+        }
+    }
+
+    /// The segment and its local position for the given position on the track.
+    #[must_use]
+    pub fn locate(&self, pos: usize) -> Option<(Entity, usize)> {
+        if pos >= self.len {
+            return None;
+        }
+        let entry = self.segments.len() - 1;
+        let idx   = if pos >= self.segment_start(entry) { entry } else { pos / TRACK_MAX_ITEMS };
+        Some((self.segments[idx], pos - self.segment_start(idx)))
+    }
+
+    /// A connection inserting onto the entry of this track.
+    #[must_use]
+    pub fn passthrough_into(&self) -> TrackPassthrough {
+        let start = self.segment_start(self.segments.len() - 1);
+        TrackPassthrough::new(self.entry(), self.len - 1 - start)
+    }
+
+}
\ No newline at end of file
@@ -5,27 +5,51 @@ use bevy::prelude::*;
 use crate::item::ItemStack;
 use super::{TrackQueue, TRACK_MAX_ITEMS};
 
+/// Which item goes first when a side-loaded item and the destination's own upstream item
+/// contend for the insertion point.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrackSideLoadPriority {
+    /// Side-loaded items are inserted whenever the insertion point is free, upstream items queue behind them.
+    #[default]
+    Side,
+    /// Side-loaded items wait until there's no upstream item directly behind the insertion point.
+    Upstream,
+}
+
 #[derive(Debug, Clone, Copy, Component, PartialEq, Eq)]
 pub struct TrackPassthrough {
     pub dst: Entity,
     pub loc: u8,
+    pub priority: TrackSideLoadPriority,
 }
 
 impl TrackPassthrough {
 
     #[must_use]
     pub const fn new(dst: Entity, into: usize) -> Self {
-        Self{dst, loc: (into + 1) as u8}
+        Self{dst, loc: (into + 1) as u8, priority: TrackSideLoadPriority::Side}
     }
 
     #[must_use]
     pub const fn new_end_to_end(dst: Entity) -> Self {
-        Self{dst, loc: TRACK_MAX_ITEMS as u8}
+        Self{dst, loc: TRACK_MAX_ITEMS as u8, priority: TrackSideLoadPriority::Side}
+    }
+
+    #[must_use]
+    pub const fn with_priority(self, priority: TrackSideLoadPriority) -> Self {
+        Self{priority, ..self}
     }
 
     #[must_use]
     pub const fn can_transfer(&self, src_queue: &TrackQueue, dst_queue: &TrackQueue) -> bool {
-        src_queue.has(0) && !dst_queue.has(self.loc as usize) && (self.loc == 0 || dst_queue.can_advance_idx(self.loc as usize))
+        src_queue.has(0) && self.can_accept(dst_queue)
+    }
+
+    #[must_use]
+    pub const fn can_accept(&self, dst_queue: &TrackQueue) -> bool {
+        let loc = self.loc as usize;
+        let yields = matches!(self.priority, TrackSideLoadPriority::Upstream) && loc + 1 < TRACK_MAX_ITEMS && dst_queue.has(loc + 1);
+        !yields && !dst_queue.has(loc) && (loc == 0 || dst_queue.can_advance_idx(loc))
     }
 
 }
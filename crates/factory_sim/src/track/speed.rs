@@ -0,0 +1,87 @@
+// Copyright 2024 Natalie Baker // AGPLv3 //
+
+use bevy::{ecs::query::QueryFilter, prelude::*};
+
+use crate::tick::{SubTickCount, TickRate};
+
+/// Moves a track `steps` positions every `ticks` ticks. Its [`TickRate`] is kept at the most steps it
+/// takes in a tick, and each tick the track systems skip the sub-ticks past that tick's steps, so
+/// fractional speeds don't change its markers. Speeds above one step per sub-tick are capped.
+#[derive(Debug, Clone, Copy, Component, PartialEq, Eq)]
+pub struct TrackSpeed {
+    steps:    u32,
+    ticks:    u32,
+    progress: u32,
+    /// Steps left in the current tick.
+    remaining: u8,
+}
+
+impl TrackSpeed {
+
+    #[must_use]
+    pub const fn new(steps: u32, ticks: u32) -> Self {
+        Self{ steps, ticks: if ticks == 0 { 1 } else { ticks }, progress: 0, remaining: 0 }
+    }
+
+    #[must_use]
+    pub const fn per_tick(steps: u32) -> Self {
+        Self::new(steps, 1)
+    }
+
+    #[must_use]
+    pub const fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    #[must_use]
+    pub const fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// The most steps taken in a tick, up to `max`.
+    #[must_use]
+    pub const fn max_steps(&self, max: u8) -> u8 {
+        let steps = self.steps.div_ceil(self.ticks);
+        if steps < max as u32 { steps as u8 } else { max }
+    }
+
+    /// Advances to the next tick, returning the steps to take in it, up to `max`.
+    pub fn advance(&mut self, max: u8) -> u8 {
+        self.progress += self.steps;
+        let steps = self.progress / self.ticks;
+        self.progress %= self.ticks;
+        self.remaining = steps.min(max as u32) as u8;
+        self.remaining
+    }
+
+    /// Whether the track has a step left in the current tick, tracks without a speed always do.
+    #[must_use]
+    pub fn has_step(speed: Option<&Self>) -> bool {
+        speed.is_none_or(|speed| speed.remaining > 0)
+    }
+
+}
+
+pub fn update_track_speeds(
+    mut q_speeds: Query<(Entity, &mut TrackSpeed, Option<&mut TickRate>)>, 
+    sub_ticks: Res<SubTickCount>,
+    mut commands: Commands
+) {
+    for (id, mut speed, tick_rate) in &mut q_speeds {
+        speed.advance(sub_ticks.get());
+        let rate = TickRate(speed.max_steps(sub_ticks.get()));
+        match tick_rate {
+            Some(mut tick_rate) => { tick_rate.set_if_neq(rate); },
+            None                => { commands.entity(id).insert(rate); },
+        }
+    }
+}
+
+/// Uses up a step of each track's speed, after the track systems in a sub-tick.
+pub fn consume_track_steps<F: QueryFilter>(mut q_speeds: Query<&mut TrackSpeed, F>) {
+    for mut speed in &mut q_speeds {
+        if speed.remaining > 0 {
+            speed.remaining -= 1;
+        }
+    }
+}
@@ -2,18 +2,31 @@
 
 use bevy::{ecs::query::QueryFilter, prelude::*};
 
-use crate::tick::{Cooldown, Tick};
-use super::{StackBuffer, TrackBuffer, TrackExtractor, TrackInserter, TrackPassthrough, TrackQueue};
+use crate::{item::{ItemFilter, ItemRegistry}, power::PowerStalled, tick::{Cooldown, Tick}};
+use super::{junction::select_connection, StackBuffer, TrackBuffer, TrackExtractor, TrackInserter, TrackMerger, TrackPassthrough, TrackQueue, TrackRouter, TrackSideLoadPriority, TrackSpeed, TrackSplitter};
 
-pub fn advance_conveyors<F: QueryFilter>(mut q_conveyors: Query<&mut TrackQueue, F>) {
-    for mut conveyor in &mut q_conveyors {
-        *conveyor = conveyor.next();
+pub type FilterReady = (Without<Cooldown>, Without<PowerStalled>);
+
+/// Entities that aren't [`PowerStalled`], along with the sub-tick filter `F`.
+pub type FilterPowered<F> = (Without<PowerStalled>, F);
+
+/// A [`StackBuffer`] along with the filter on what may pass through it.
+pub type FilteredStackBuffer<'a> = (&'a mut StackBuffer, Option<&'a ItemFilter>);
+
+pub fn advance_conveyors<F: QueryFilter>(mut q_conveyors: Query<(&mut TrackQueue, Option<&TrackSpeed>), FilterPowered<F>>) {
+    for (mut conveyor, speed) in &mut q_conveyors {
+        if TrackSpeed::has_step(speed) {
+            *conveyor = conveyor.next();
+        }
     }
 }
 
 #[allow(clippy::missing_panics_doc)]
-pub fn handle_track_passthrough<F: QueryFilter>(q_connections: Query<(Entity, &TrackPassthrough), F>, mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>) {
-    for (src_ent, connection) in &q_connections {
+pub fn handle_track_passthrough<F: QueryFilter>(q_connections: Query<(Entity, &TrackPassthrough, Option<&TrackSpeed>), FilterPowered<F>>, mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>) {
+    for (src_ent, connection, speed) in &q_connections {
+        if !TrackSpeed::has_step(speed) {
+            continue;
+        }
 
         let can_transfer = {
             let [(src_queue, _), (dst_queue, _)] = q_conveyors.get_many([src_ent, connection.dst]).unwrap();
@@ -21,28 +34,95 @@ pub fn handle_track_passthrough<F: QueryFilter>(q_connections: Query<(Entity, &T
         };
 
         if can_transfer {
-            let item = {
-                let (mut src_queue, mut src_buffer) = q_conveyors.get_mut(src_ent).unwrap();
-                *src_queue = src_queue.without(0);
-                src_buffer.pop().unwrap()
-            };
+            transfer_head(&mut q_conveyors, src_ent, connection);
+        }
+    }
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn handle_track_splitters<F: QueryFilter>(mut q_splitters: Query<(Entity, &mut TrackSplitter, Option<&TrackSpeed>), FilterPowered<F>>, mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>) {
+    for (src_ent, mut splitter, speed) in &mut q_splitters {
+        if !TrackSpeed::has_step(speed) || !q_conveyors.get(src_ent).unwrap().0.has(0) {
+            continue;
+        }
+
+        let splitter = &mut *splitter;
+        let selected = select_connection(splitter.mode, &mut splitter.next, splitter.outputs.len(), |i| {
+            let output = &splitter.outputs[i];
+            output.can_accept(q_conveyors.get(output.dst).unwrap().0)
+        });
 
-            let (mut dst_queue, mut dst_buffer) = q_conveyors.get_mut(connection.dst).unwrap();
-            *dst_queue = dst_queue.with(connection.loc as usize);
-            let idx = dst_queue.get_buffer_index_of(connection.loc as usize);
-            dst_buffer.insert(idx, item).unwrap();
+        if let Some(idx) = selected {
+            transfer_head(&mut q_conveyors, src_ent, &splitter.outputs[idx]);
         }
     }
 }
 
+#[allow(clippy::missing_panics_doc)]
+pub fn handle_track_mergers<F: QueryFilter>(mut q_mergers: Query<(Entity, &mut TrackMerger, Option<&TrackSpeed>), FilterPowered<F>>, mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>) {
+    for (dst_ent, mut merger, speed) in &mut q_mergers {
+        let connection = TrackPassthrough{ dst: dst_ent, loc: merger.loc, priority: TrackSideLoadPriority::Side };
+        if !TrackSpeed::has_step(speed) || !connection.can_accept(q_conveyors.get(dst_ent).unwrap().0) {
+            continue;
+        }
+
+        let merger = &mut *merger;
+        let selected = select_connection(merger.mode, &mut merger.next, merger.inputs.len(), |i| {
+            q_conveyors.get(merger.inputs[i]).unwrap().0.has(0)
+        });
+
+        if let Some(idx) = selected {
+            transfer_head(&mut q_conveyors, merger.inputs[idx], &connection);
+        }
+    }
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn handle_track_routers<F: QueryFilter>(
+    q_routers: Query<(Entity, &TrackRouter, Option<&TrackSpeed>), FilterPowered<F>>, 
+    mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>,
+    registry: Option<Res<ItemRegistry>>,
+) {
+    for (src_ent, router, speed) in &q_routers {
+        let (src_queue, src_buffer) = q_conveyors.get(src_ent).unwrap();
+        if !TrackSpeed::has_step(speed) || !src_queue.has(0) {
+            continue;
+        }
+
+        let item = src_buffer.get(0).unwrap().item();
+        let selected = router.route(item, registry.as_deref()).into_iter().chain(&router.fallback).find(|output| {
+            output.can_accept(q_conveyors.get(output.dst).unwrap().0)
+        });
+
+        if let Some(output) = selected {
+            transfer_head(&mut q_conveyors, src_ent, output);
+        }
+    }
+}
+
+/// Moves the item at the head of the source track through the connection, without checking it can transfer.
+fn transfer_head(q_conveyors: &mut Query<(&mut TrackQueue, &mut TrackBuffer)>, src_ent: Entity, connection: &TrackPassthrough) {
+    let item = {
+        let (mut src_queue, mut src_buffer) = q_conveyors.get_mut(src_ent).unwrap();
+        *src_queue = src_queue.without(0);
+        src_buffer.pop().unwrap()
+    };
+
+    let (mut dst_queue, mut dst_buffer) = q_conveyors.get_mut(connection.dst).unwrap();
+    *dst_queue = dst_queue.with(connection.loc as usize);
+    let idx = dst_queue.get_buffer_index_of(connection.loc as usize);
+    dst_buffer.insert(idx, item).unwrap();
+}
+
 #[allow(clippy::missing_panics_doc)]
 pub fn handle_track_stack_extractors<F: QueryFilter>(
-    mut q_extractors: Query<(Entity, &TrackExtractor, &mut StackBuffer), (Without<Cooldown>, F)>, 
+    mut q_extractors: Query<(Entity, &TrackExtractor, FilteredStackBuffer), (FilterReady, F)>, 
     mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>,
     mut commands: Commands,
     tick: Res<Tick>,
+    registry: Option<Res<ItemRegistry>>,
 ) {
-    for (id, extractor, mut dst_buffer) in &mut q_extractors {
+    for (id, extractor, (mut dst_buffer, filter)) in &mut q_extractors {
         if dst_buffer.contents.is_some() {
             continue;
         }
@@ -53,6 +133,10 @@ pub fn handle_track_stack_extractors<F: QueryFilter>(
         }
 
         let idx = src_queue.get_buffer_index_of(extractor.loc);
+        if !ItemFilter::allows(filter, src_buffer.get(idx).unwrap().item(), registry.as_deref()) {
+            continue;
+        }
+
         *src_queue = src_queue.without(extractor.loc);
         dst_buffer.contents = src_buffer.remove(idx);
         if extractor.cooldown > 0 {
@@ -63,13 +147,18 @@ pub fn handle_track_stack_extractors<F: QueryFilter>(
 
 #[allow(clippy::missing_panics_doc)]
 pub fn handle_track_stack_inserters<F: QueryFilter>(
-    mut q_extractors: Query<(Entity, &TrackInserter, &mut StackBuffer), (Without<Cooldown>, F)>, 
+    mut q_extractors: Query<(Entity, &TrackInserter, FilteredStackBuffer), (FilterReady, F)>, 
     mut q_conveyors: Query<(&mut TrackQueue, &mut TrackBuffer)>,
     mut commands: Commands,
     tick: Res<Tick>,
+    registry: Option<Res<ItemRegistry>>,
 ) {
-    for (id, inserter, mut src_buffer) in &mut q_extractors {
-        if src_buffer.contents.is_none() {
+    for (id, inserter, (mut src_buffer, filter)) in &mut q_extractors {
+        let Some(stack) = src_buffer.contents else {
+            continue;
+        };
+
+        if !ItemFilter::allows(filter, stack.item(), registry.as_deref()) {
             continue;
         }
 
@@ -1,12 +1,14 @@
 // Copyright 2024 Natalie Baker // AGPLv3 //
 
 use bevy::prelude::*;
+use nvm_str_id::SmolStr;
 
 use crate::{
-    item::ItemStack, 
+    item::{ItemFilter, ItemFilterEntry, ItemRegistryBuilder, ItemStack}, 
     plugin::PluginsFactory, 
-    tick::{TickPacer, TickRate1}, 
-    track::{StackBuffer, TrackBuffer, TrackExtractor, TrackInserter, TrackPassthrough, TrackQueue, TRACK_MAX_ITEMS}
+    test::create_app, 
+    tick::{TickPacer, TickRate, SUB_TICK_DEFAULT}, 
+    track::{StackBuffer, TrackBelt, TrackBeltLane, TrackBuffer, TrackExtractor, TrackInserter, TrackJunctionMode, TrackLane, TrackMerger, TrackPassthrough, TrackQueue, TrackRouter, TrackSegments, TrackSideLoadPriority, TrackSpeed, TrackSplitter, TRACK_MAX_ITEMS}
 };
 
 #[test]
@@ -21,14 +23,11 @@ pub fn test_conveyor_loop() {
         buffer
     };
 
-    let mut app = App::new();
-    app.add_plugins(PluginsFactory{
-        pacer: TickPacer::unpaced(),
-    });
+    let mut app = create_app(TickPacer::unpaced());
 
-    let ent1 = app.world.spawn((queue, buffer_with(stack_1), TickRate1)).id();
-    let ent2 = app.world.spawn((queue, buffer_with(stack_2), TickRate1)).id();
-    let ent3 = app.world.spawn((queue, buffer_with(stack_1), TickRate1)).id();
+    let ent1 = app.world.spawn((queue, buffer_with(stack_1), TickRate(1))).id();
+    let ent2 = app.world.spawn((queue, buffer_with(stack_2), TickRate(1))).id();
+    let ent3 = app.world.spawn((queue, buffer_with(stack_1), TickRate(1))).id();
 
     app.world.get_entity_mut(ent1).unwrap().insert(TrackPassthrough::new_end_to_end(ent2)); // 1 loops with 2
     app.world.get_entity_mut(ent2).unwrap().insert(TrackPassthrough::new_end_to_end(ent1));
@@ -65,12 +64,9 @@ pub fn test_self_inserter() {
         buffer
     };
 
-    let mut app = App::new();
-    app.add_plugins(PluginsFactory{
-        pacer: TickPacer::unpaced(),
-    });
+    let mut app = create_app(TickPacer::unpaced());
 
-    let track = app.world.spawn((queue, buffer, TickRate1)).id();
+    let track = app.world.spawn((queue, buffer, TickRate(1))).id();
     let _mover = app.world.spawn((
         TrackInserter{
             target: track,
@@ -85,7 +81,7 @@ pub fn test_self_inserter() {
         StackBuffer{
             contents: None,
         },
-        TickRate1
+        TickRate(1)
     )).id();
 
     // TODO finish this test
@@ -98,4 +94,344 @@ pub fn test_self_inserter() {
         if i != TRACK_MAX_ITEMS { app.update(); }
     }
 
-}
\ No newline at end of file
+}
+#[test]
+pub fn test_filtered_extractor() {
+    let mut items = ItemRegistryBuilder::default();
+    let iron   = items.register(SmolStr::new("iron"),   "base".to_owned()).unwrap();
+    let copper = items.register(SmolStr::new("copper"), "base".to_owned()).unwrap();
+    items.info_mut(SmolStr::new("iron")).unwrap().add_tag(SmolStr::new("metal"));
+    let items = items.build();
+
+    let metal = ItemFilter::whitelist([ItemFilterEntry::Tag(SmolStr::new("metal"))]);
+    assert!( metal.matches(iron,   Some(&items)));
+    assert!(!metal.matches(copper, Some(&items)));
+    assert!(!metal.matches(iron,   None));
+    assert!( ItemFilter::blacklist([ItemFilterEntry::Item(copper)]).matches(iron, None));
+    assert!(!ItemFilter::blacklist([ItemFilterEntry::Item(copper)]).matches(copper, None));
+
+    let mut app = create_app(TickPacer::unpaced());
+    app.insert_resource(items);
+
+    let track_in = app.world.spawn((
+        TrackQueue::default().with(1).with(3),
+        {
+            let mut buffer = TrackBuffer::default();
+            buffer.push(copper.as_stack(1)).unwrap();
+            buffer.push(iron.as_stack(1)).unwrap();
+            buffer
+        },
+        TickRate(1),
+    )).id();
+    app.world.entity_mut(track_in).insert(TrackPassthrough::new_end_to_end(track_in));
+    let track_out = app.world.spawn((TrackQueue::default(), TrackBuffer::default(), TickRate(1))).id();
+
+    app.world.spawn((
+        TrackExtractor{ target: track_in, loc: 0, cooldown: 0 },
+        TrackInserter{ target: track_out, loc: TRACK_MAX_ITEMS - 1, cooldown: 0 },
+        StackBuffer{ contents: None },
+        metal,
+        TickRate(1),
+    ));
+
+    // Iron is sorted out, copper is left looping on the input
+    for _ in 0..TRACK_MAX_ITEMS*2 {
+        app.update();
+    }
+    assert_eq!(&[copper.as_stack(1)], app.world.get::<TrackBuffer>(track_in ).unwrap().as_slice());
+    assert_eq!(&[iron.as_stack(1)],   app.world.get::<TrackBuffer>(track_out).unwrap().as_slice());
+}
+
+fn spawn_track_with(app: &mut App, stacks: &[ItemStack]) -> Entity {
+    let mut queue  = TrackQueue::default();
+    let mut buffer = TrackBuffer::default();
+    for (i, &stack) in stacks.iter().enumerate() {
+        queue = queue.with(i);
+        buffer.push(stack).unwrap();
+    }
+    app.world.spawn((queue, buffer, TickRate(1))).id()
+}
+
+#[test]
+pub fn test_splitter_round_robin() {
+    let stacks: Vec<_> = (1..=4).map(|i| ItemStack::from_raw(i, 1)).collect();
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let src   = spawn_track_with(&mut app, &stacks);
+    let dst_a = spawn_track_with(&mut app, &[]);
+    let dst_b = spawn_track_with(&mut app, &[]);
+    app.world.entity_mut(src).insert(TrackSplitter::new([
+        TrackPassthrough::new_end_to_end(dst_a),
+        TrackPassthrough::new_end_to_end(dst_b),
+    ], TrackJunctionMode::RoundRobin));
+
+    for _ in 0..8 {
+        app.update();
+    }
+
+    assert!(app.world.get::<TrackBuffer>(src).unwrap().is_empty());
+    assert_eq!(&[stacks[0], stacks[2]], app.world.get::<TrackBuffer>(dst_a).unwrap().as_slice());
+    assert_eq!(&[stacks[1], stacks[3]], app.world.get::<TrackBuffer>(dst_b).unwrap().as_slice());
+}
+
+#[test]
+pub fn test_splitter_priority_overflow() {
+    let stacks: Vec<_> = (1..=3).map(|i| ItemStack::from_raw(i, 1)).collect();
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    // The preferred output is blocked at its insertion point, so items overflow into the second
+    let src   = spawn_track_with(&mut app, &stacks);
+    let dst_a = app.world.spawn((TrackQueue::default().with(TRACK_MAX_ITEMS - 1), {
+        let mut buffer = TrackBuffer::default();
+        buffer.push(ItemStack::from_raw(5, 1)).unwrap();
+        buffer
+    })).id();
+    let dst_b = spawn_track_with(&mut app, &[]);
+    app.world.entity_mut(src).insert(TrackSplitter::new([
+        TrackPassthrough::new(dst_a, TRACK_MAX_ITEMS - 2),
+        TrackPassthrough::new_end_to_end(dst_b),
+    ], TrackJunctionMode::Priority));
+
+    for _ in 0..6 {
+        app.update();
+    }
+
+    assert_eq!(&[ItemStack::from_raw(5, 1)], app.world.get::<TrackBuffer>(dst_a).unwrap().as_slice());
+    assert_eq!(stacks.as_slice(), app.world.get::<TrackBuffer>(dst_b).unwrap().as_slice());
+}
+
+#[test]
+pub fn test_merger_priority() {
+    let stacks_a: Vec<_> = (1..=2).map(|i| ItemStack::from_raw(i, 1)).collect();
+    let stacks_b: Vec<_> = (3..=4).map(|i| ItemStack::from_raw(i, 1)).collect();
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let src_a = spawn_track_with(&mut app, &stacks_a);
+    let src_b = spawn_track_with(&mut app, &stacks_b);
+    let dst   = spawn_track_with(&mut app, &[]);
+    app.world.entity_mut(dst).insert(TrackMerger::new_end_to_end([src_b, src_a], TrackJunctionMode::Priority));
+
+    for _ in 0..8 {
+        app.update();
+    }
+
+    let expected = [stacks_b[0], stacks_b[1], stacks_a[0], stacks_a[1]];
+    assert!(app.world.get::<TrackBuffer>(src_a).unwrap().is_empty());
+    assert!(app.world.get::<TrackBuffer>(src_b).unwrap().is_empty());
+    assert_eq!(&expected, app.world.get::<TrackBuffer>(dst).unwrap().as_slice());
+}
+
+#[test]
+pub fn test_router_fallback() {
+    let iron   = ItemStack::from_raw(1, 1);
+    let copper = ItemStack::from_raw(2, 1);
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let src      = spawn_track_with(&mut app, &[iron, copper, iron, iron]);
+    let dst_iron = app.world.spawn((TrackQueue::default(), TrackBuffer::default())).id();
+    let dst_rest = spawn_track_with(&mut app, &[]);
+    app.world.entity_mut(src).insert(TrackRouter::new([(
+        ItemFilter::whitelist([ItemFilterEntry::Item(iron.item())]), 
+        TrackPassthrough::new_end_to_end(dst_iron),
+    )], Some(TrackPassthrough::new_end_to_end(dst_rest))));
+
+    for _ in 0..8 {
+        app.update();
+    }
+
+    // The iron output doesn't advance, so once it's filled the remaining iron overflows
+    assert!(app.world.get::<TrackBuffer>(src).unwrap().is_empty());
+    assert_eq!(&[iron],               app.world.get::<TrackBuffer>(dst_iron).unwrap().as_slice());
+    assert_eq!(&[copper, iron, iron], app.world.get::<TrackBuffer>(dst_rest).unwrap().as_slice());
+}
+
+#[test]
+pub fn test_belt_lanes() {
+    let stack_1 = ItemStack::from_raw(1, 1);
+    let stack_2 = ItemStack::from_raw(2, 1);
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let belt_a = TrackBelt::spawn(&mut app.world, TickRate(1));
+    let belt_b = TrackBelt::spawn(&mut app.world, TickRate(1));
+    let (belt_a, belt_b) = (*app.world.get::<TrackBelt>(belt_a).unwrap(), *app.world.get::<TrackBelt>(belt_b).unwrap());
+    for (src, connection) in belt_a.connect_to(&belt_b) {
+        app.world.entity_mut(src).insert(connection);
+    }
+    assert_eq!(TrackLane::Right, app.world.get::<TrackBeltLane>(belt_b.lane(TrackLane::Right)).unwrap().lane);
+
+    // Side load both lanes, with the right lane of the second belt stopped so it blocks once it's received an item
+    let left  = spawn_track_with(&mut app, &[stack_1]);
+    let right = spawn_track_with(&mut app, &[stack_2, stack_1]);
+    app.world.entity_mut(left ).insert(belt_a.side_load(TrackLane::Left,  TRACK_MAX_ITEMS/2));
+    app.world.entity_mut(right).insert(belt_a.side_load(TrackLane::Right, TRACK_MAX_ITEMS/2));
+    app.world.entity_mut(belt_b.lane(TrackLane::Right)).remove::<TickRate>();
+
+    for _ in 0..TRACK_MAX_ITEMS {
+        app.update();
+    }
+
+    // The left lane flows onto the next belt, whilst the right lane backs up on the first
+    assert_eq!(&[stack_1], app.world.get::<TrackBuffer>(belt_b.lane(TrackLane::Left)).unwrap().as_slice());
+    assert!(app.world.get::<TrackBuffer>(belt_a.lane(TrackLane::Left)).unwrap().is_empty());
+    assert_eq!(&[stack_2], app.world.get::<TrackBuffer>(belt_b.lane(TrackLane::Right)).unwrap().as_slice());
+    assert_eq!(TrackQueue::default().with(0), *app.world.get::<TrackQueue>(belt_a.lane(TrackLane::Right)).unwrap());
+    assert_eq!(&[stack_1], app.world.get::<TrackBuffer>(belt_a.lane(TrackLane::Right)).unwrap().as_slice());
+}
+
+fn run_side_load_contention(rate: TickRate, priority: TrackSideLoadPriority) -> Vec<ItemStack> {
+    let upstream = ItemStack::from_raw(1, 1);
+    let side     = ItemStack::from_raw(2, 1);
+    let join     = TRACK_MAX_ITEMS/2;
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    // Two upstream items directly behind the join point, contending with the side-loaded item
+    let dst = app.world.spawn((
+        TrackQueue::default().with(join + 1).with(join + 2),
+        {
+            let mut buffer = TrackBuffer::default();
+            buffer.push(upstream).unwrap();
+            buffer.push(upstream).unwrap();
+            buffer
+        },
+        rate,
+    )).id();
+    let src = app.world.spawn((TrackQueue::default().with(0), {
+        let mut buffer = TrackBuffer::default();
+        buffer.push(side).unwrap();
+        buffer
+    }, rate, TrackPassthrough::new(dst, join - 1).with_priority(priority))).id();
+
+    for _ in 0..8 {
+        app.update();
+    }
+
+    assert!(app.world.get::<TrackBuffer>(src).unwrap().is_empty());
+    app.world.get::<TrackBuffer>(dst).unwrap().as_slice().to_vec()
+}
+
+#[test]
+pub fn test_side_load_priority() {
+    let upstream = ItemStack::from_raw(1, 1);
+    let side     = ItemStack::from_raw(2, 1);
+
+    let side_first     = vec![side, upstream, upstream];
+    let upstream_first = vec![upstream, upstream, side];
+
+    assert_eq!(side_first,     run_side_load_contention(TickRate(1), TrackSideLoadPriority::Side));
+    assert_eq!(side_first,     run_side_load_contention(TickRate(2), TrackSideLoadPriority::Side));
+    assert_eq!(side_first,     run_side_load_contention(TickRate(3), TrackSideLoadPriority::Side));
+    assert_eq!(side_first,     run_side_load_contention(TickRate(4), TrackSideLoadPriority::Side));
+    assert_eq!(upstream_first, run_side_load_contention(TickRate(1), TrackSideLoadPriority::Upstream));
+    assert_eq!(upstream_first, run_side_load_contention(TickRate(2), TrackSideLoadPriority::Upstream));
+    assert_eq!(upstream_first, run_side_load_contention(TickRate(3), TrackSideLoadPriority::Upstream));
+    assert_eq!(upstream_first, run_side_load_contention(TickRate(4), TrackSideLoadPriority::Upstream));
+}
+
+#[test]
+pub fn test_track_segments() {
+    let stack = ItemStack::from_raw(1, 1);
+    let len   = TRACK_MAX_ITEMS*2 + 10;
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let track = TrackSegments::spawn(&mut app.world, len, TickRate(1));
+    let track = app.world.get::<TrackSegments>(track).unwrap().clone();
+    assert_eq!(3, track.segments.len());
+    assert_eq!(Some((track.segments[1], 5)), track.locate(TRACK_MAX_ITEMS + 5));
+    assert_eq!(Some((track.segments[1], 9)), track.locate(TRACK_MAX_ITEMS + 9));
+    assert_eq!(Some((track.entry(), 0)), track.locate(TRACK_MAX_ITEMS + 10));
+    assert_eq!(Some((track.entry(), TRACK_MAX_ITEMS - 1)), track.locate(len - 1));
+    assert_eq!(None, track.locate(len));
+
+    // The entry is full length, so upstream tracks can connect end-to-end
+    assert_eq!(TrackPassthrough::new_end_to_end(track.entry()), track.passthrough_into());
+    let src = spawn_track_with(&mut app, &[stack]);
+    app.world.entity_mut(src).insert(TrackPassthrough::new_end_to_end(track.entry()));
+
+    // The item passes through every segment, taking a tick per position
+    for _ in 0..len {
+        app.update();
+    }
+    assert!(app.world.get::<TrackBuffer>(track.entry()).unwrap().is_empty());
+    assert_eq!(TrackQueue::default().with(0), *app.world.get::<TrackQueue>(track.exit()).unwrap());
+    assert_eq!(&[stack], app.world.get::<TrackBuffer>(track.exit()).unwrap().as_slice());
+}
+
+#[test]
+pub fn test_track_speed() {
+    let stack = ItemStack::from_raw(1, 1);
+
+    let mut app = create_app(TickPacer::unpaced());
+
+    let mut spawn_with_speed = |speed: TrackSpeed| app.world.spawn((
+        TrackQueue::default().with(TRACK_MAX_ITEMS - 1),
+        {
+            let mut buffer = TrackBuffer::default();
+            buffer.push(stack).unwrap();
+            buffer
+        },
+        speed,
+    )).id();
+
+    let slow    = spawn_with_speed(TrackSpeed::new(1, 2));
+    let fast    = spawn_with_speed(TrackSpeed::new(3, 2));
+    let capped  = spawn_with_speed(TrackSpeed::per_tick(6));
+    let stopped = spawn_with_speed(TrackSpeed::per_tick(0));
+
+    for _ in 0..6 {
+        app.update();
+    }
+
+    let position = |app: &App, id: Entity| app.world.get::<TrackQueue>(id).unwrap().iter().next().unwrap();
+    assert_eq!(TRACK_MAX_ITEMS - 1 -  3, position(&app, slow));
+    assert_eq!(TRACK_MAX_ITEMS - 1 -  9, position(&app, fast));
+    assert_eq!(TRACK_MAX_ITEMS - 1 - 24, position(&app, capped));
+    assert_eq!(TRACK_MAX_ITEMS - 1,      position(&app, stopped));
+
+    // Tick rates stay at the most steps per tick, rather than changing each tick
+    let rate = |app: &App, id: Entity| *app.world.get::<TickRate>(id).unwrap();
+    assert_eq!(
+        [TickRate(1), TickRate(2), TickRate(SUB_TICK_DEFAULT), TickRate(0)], 
+        [rate(&app, slow), rate(&app, fast), rate(&app, capped), rate(&app, stopped)]
+    );
+}
+
+#[test]
+pub fn test_sub_tick_count() {
+    let stack = ItemStack::from_raw(1, 1);
+
+    let mut app = App::new();
+    app.add_plugins(PluginsFactory::default().with_sub_ticks(6));
+
+    let mut spawn_with_rate = |rate: TickRate| app.world.spawn((
+        TrackQueue::default().with(TRACK_MAX_ITEMS - 1),
+        {
+            let mut buffer = TrackBuffer::default();
+            buffer.push(stack).unwrap();
+            buffer
+        },
+        rate,
+    )).id();
+
+    let fastest = spawn_with_rate(TickRate(6));
+    let capped  = spawn_with_rate(TickRate(8));
+    let slowed  = spawn_with_rate(TickRate(5));
+
+    app.update();
+    app.update();
+
+    // Rates can be changed at runtime
+    app.world.entity_mut(slowed).insert(TickRate(1));
+    app.update();
+
+    let position = |app: &App, id: Entity| app.world.get::<TrackQueue>(id).unwrap().iter().next().unwrap();
+    assert_eq!(TRACK_MAX_ITEMS - 1 - 18, position(&app, fastest));
+    assert_eq!(TRACK_MAX_ITEMS - 1 - 18, position(&app, capped));
+    assert_eq!(TRACK_MAX_ITEMS - 1 - 11, position(&app, slowed));
+}